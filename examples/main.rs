@@ -17,18 +17,21 @@ struct Point {
     y: i32,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonTest {
     title: String,
     content: String,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 struct ParamTest {
     test: Vec<String>,
     test2: String,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 struct User {
     name: String,
@@ -41,34 +44,32 @@ impl Display for JsonTest {
     }
 }
 
-// async fn responder_json(mut ctx: Context) -> impl Responder {
-//     let person: Person = ctx.json().await?;
+async fn responder_json(mut ctx: Context) -> Result<Response, ObsidianError> {
+    let mut user: User = ctx.json().await?;
 
-//     person.age += 1;
+    user.age += 1;
 
-//     Ok(response::json(person))
-// }
-
-// async fn responder_obsidian_error(mut ctx: Context) -> impl Responder {
-//     let json: JsonTest = ctx.json().await?;
-//     println!("{}", json);
-//     Ok(response::json(json, StatusCode::OK))
-// }
+    Ok(Response::ok().json(user))
+}
 
-// fn responder_with_header(ctx: Context: Context) -> impl Responder {
-//     let headers = vec![
-//         ("X-Custom-Header-4", "custom-value-4"),
-//         ("X-Custom-Header-5", "custom-value-5"),
-//     ];
+async fn responder_obsidian_error(mut ctx: Context) -> Result<impl Responder, ObsidianError> {
+    let json: JsonTest = ctx.json().await?;
+    println!("{}", json);
+    Ok(Response::ok().json(json))
+}
 
-//     "here"
-//         .header("Content-Type", "application/json")
-//         .header("X-Custom-Header", "custom-value")
-//         .header("X-Custom-Header-2", "custom-value-2")
-//         .header("X-Custom-Header-3", "custom-value-3")
-//         .set_headers(headers)
-//         .status(StatusCode::CREATED)
-// }
+async fn responder_with_header(_ctx: Context) -> impl Responder {
+    let headers = vec![
+        ("X-Custom-Header-4", "custom-value-4"),
+        ("X-Custom-Header-5", "custom-value-5"),
+    ];
+
+    "here"
+        .with_header(header::CONTENT_TYPE, "application/json")
+        .set_header_str("X-Custom-Header", "custom-value")
+        .set_headers_str(headers)
+        .set_status(StatusCode::CREATED)
+}
 
 #[tokio::main]
 async fn main() {
@@ -111,6 +112,10 @@ ctx.build(Response::ok().html("<!DOCTYPE html><html><head><link rel=\"shotcut ic
         ctx.build("").ok()
     });
 
+    app.post("/responder/json", responder_json);
+    app.post("/responder/error", responder_obsidian_error);
+    app.get("/responder/header", responder_with_header);
+
     app.patch("/patch-here", |ctx: Context| async {
         ctx.build("Here is patch request").ok()
     });
@@ -191,11 +196,7 @@ ctx.build(Response::ok().html("<!DOCTYPE html><html><head><link rel=\"shotcut ic
     });
 
     app.get("/test/wildcard/*", |ctx: Context| async move {
-        let res = format!(
-            "{}<br>{}",
-            "<h1>Test wildcard</h1>".to_string(),
-            ctx.uri().path()
-        );
+        let res = format!("{}<br>{}", "<h1>Test wildcard</h1>", ctx.uri().path());
 
         ctx.build(res).ok()
     });
@@ -210,46 +211,30 @@ ctx.build(Response::ok().html("<!DOCTYPE html><html><head><link rel=\"shotcut ic
 
         let res = Some(format!(
             "{}<br>{}",
-            "<h1>router test get</h1>".to_string(),
+            "<h1>router test get</h1>",
             ctx.uri().path()
         ));
 
         ctx.build(res).ok()
     });
     app.post("router/test", |ctx: Context| async move {
-        let res = format!(
-            "{}<br>{}",
-            "<h1>router test post</h1>".to_string(),
-            ctx.uri().path()
-        );
+        let res = format!("{}<br>{}", "<h1>router test post</h1>", ctx.uri().path());
 
         ctx.build(res).ok()
     });
     app.put("router/test", |ctx: Context| async move {
-        let res = format!(
-            "{}<br>{}",
-            "<h1>router test put</h1>".to_string(),
-            ctx.uri().path()
-        );
+        let res = format!("{}<br>{}", "<h1>router test put</h1>", ctx.uri().path());
 
         ctx.build(res).ok()
     });
     app.delete("router/test", |ctx: Context| async move {
-        let res = format!(
-            "{}<br>{}",
-            "<h1>router test delete</h1>".to_string(),
-            ctx.uri().path()
-        );
+        let res = format!("{}<br>{}", "<h1>router test delete</h1>", ctx.uri().path());
 
         ctx.build(res).ok()
     });
 
     app.get("route/diff_route", |ctx: Context| async move {
-        let res = format!(
-            "{}<br>{}",
-            "<h1>route diff get</h1>".to_string(),
-            ctx.uri().path()
-        );
+        let res = format!("{}<br>{}", "<h1>route diff get</h1>", ctx.uri().path());
 
         ctx.build(res).ok()
     });
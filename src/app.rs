@@ -1,16 +1,26 @@
+use std::collections::HashMap;
+use std::future::{self, Future};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
 use std::sync::Arc;
+use std::time::Duration;
 
 use colored::*;
 use hyper::{
+    body::HttpBody,
     header,
+    http::uri::Authority,
     service::{make_service_fn, service_fn},
-    Body, Request, Response, Server, StatusCode,
+    Body, Method, Request, Response, Server, StatusCode, Uri,
 };
+use tokio::{signal, sync::oneshot, time};
 
 use crate::context::Context;
-use crate::error::ObsidianError;
+use crate::error::{MultipartError, ObsidianError, RouteError};
 use crate::middleware::Middleware;
-use crate::router::{ContextResult, Handler, RouteValueResult, Router};
+use crate::router::{
+    ContextResult, Handler, HostPattern, IntoHandler, Route, RouteBuilder, RouteInfo, RouteParams,
+    Router, TrailingSlash,
+};
 
 use crate::middleware::logger::Logger;
 
@@ -22,7 +32,11 @@ where
     T: Clone + Send + Sync + 'static,
 {
     router: Router,
+    /// Routers of the hosts, the static hosts are placed before the host patterns with params
+    hosts: Vec<(HostPattern, Router)>,
     app_state: Option<T>,
+    shutdown_timeout: Option<Duration>,
+    print_routes: bool,
 }
 
 impl<T> Default for App<T>
@@ -33,7 +47,10 @@ where
     fn default() -> Self {
         let mut app = App {
             router: Router::new(),
+            hosts: Vec::new(),
             app_state: None,
+            shutdown_timeout: None,
+            print_routes: false,
         };
         let logger = Logger::new();
         app.use_service(logger);
@@ -48,42 +65,83 @@ where
     pub fn new() -> Self {
         App {
             router: Router::new(),
+            hosts: Vec::new(),
             app_state: None,
+            shutdown_timeout: None,
+            print_routes: false,
         }
     }
 
-    pub fn get(&mut self, path: &str, handler: impl Handler) {
-        self.router.get(path, handler);
+    pub fn get<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        self.router.get(path, handler)
+    }
+
+    pub fn post<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        self.router.post(path, handler)
+    }
+
+    pub fn put<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        self.router.put(path, handler)
+    }
+
+    pub fn patch<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        self.router.patch(path, handler)
+    }
+
+    pub fn delete<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> RouteBuilder<'_> {
+        self.router.delete(path, handler)
     }
 
-    pub fn post(&mut self, path: &str, handler: impl Handler) {
-        self.router.post(path, handler);
+    pub fn head<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        self.router.head(path, handler)
     }
 
-    pub fn put(&mut self, path: &str, handler: impl Handler) {
-        self.router.put(path, handler);
+    pub fn options<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> RouteBuilder<'_> {
+        self.router.options(path, handler)
     }
 
-    pub fn patch(&mut self, path: &str, handler: impl Handler) {
-        self.router.patch(path, handler);
+    /// Register a handler for any request method, including custom methods such as `PROPFIND`
+    pub fn route<Args>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> RouteBuilder<'_> {
+        self.router.route(method, path, handler)
     }
 
-    pub fn delete(&mut self, path: &str, handler: impl Handler) {
-        self.router.delete(path, handler);
+    /// Register a fallback handler which is used when no method specific handler matches
+    pub fn any<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        self.router.any(path, handler)
     }
 
-    /// Register a nested router for the app
+    /// Register a nested router for the app.
+    /// Middlewares applied through `use_service` in the closure apply to every route of the scope,
+    /// while `with` on a route applies to that route only
     ///
     /// Example:
     /// ```
-    /// use obsidian::{App, router::Router, context::Context};
+    /// use obsidian::{App, router::Router, context::Context, middleware::logger::Logger};
     ///
     /// let mut app: App = App::new();
     ///
     /// app.scope("admin", |router: &mut Router| {
+    ///     router.use_service(Logger::new());
+    ///
     ///     router.get("list", |ctx: Context| async move {
     ///         ctx.build("Admin list here").ok()
     ///     });
+    ///     router
+    ///         .post("list", |ctx: Context| async move { ctx.build("Created").ok() })
+    ///         .with(Logger::new());
     /// });
     /// ```
     ///
@@ -94,6 +152,44 @@ where
         self.use_router(format!("/{}", name).as_ref(), new_router);
     }
 
+    /// Register the routes served for the requests to the host, matched against the `Host` header
+    /// or the authority of the request uri. Labels of the host pattern can be params such as
+    /// `:tenant.example.com`, which are read through `Context::param` with the path params.
+    /// Requests to the unregistered hosts are served by the default routes of the app.
+    ///
+    /// The middlewares applied through `use_service` apply to the host routes as well,
+    /// while the routing settings of the app do not.
+    /// Panic if the host pattern is invalid
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, App};
+    ///
+    /// let mut app: App = App::new();
+    ///
+    /// app.host("api.example.com", |router| {
+    ///     router.get("/", |ctx: Context| async { ctx.build("api").ok() });
+    /// });
+    ///
+    /// app.host(":tenant.example.com", |router| {
+    ///     router.get("/", |ctx: Context| async {
+    ///         let tenant: String = ctx.param("tenant")?;
+    ///
+    ///         ctx.build(tenant).ok()
+    ///     });
+    /// });
+    /// ```
+    pub fn host(&mut self, host: &str, host_routes: impl Fn(&mut Router)) {
+        if let Err(err) = self.try_host(host, host_routes) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Set the handler for the requests which match no route, see [`Router::fallback`]
+    pub fn fallback<Args>(&mut self, handler: impl IntoHandler<Args>) {
+        self.router.fallback(handler);
+    }
+
     /// Apply middleware in the provided route
     pub fn use_service_to(&mut self, path: &str, middleware: impl Middleware) {
         self.router.use_service_to(path, middleware);
@@ -119,6 +215,150 @@ where
         self.router.use_static(dir_path);
     }
 
+    pub fn try_get<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_get(path, handler)
+    }
+
+    pub fn try_post<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_post(path, handler)
+    }
+
+    pub fn try_put<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_put(path, handler)
+    }
+
+    pub fn try_patch<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_patch(path, handler)
+    }
+
+    pub fn try_delete<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_delete(path, handler)
+    }
+
+    pub fn try_head<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_head(path, handler)
+    }
+
+    pub fn try_options<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_options(path, handler)
+    }
+
+    pub fn try_route<Args>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_route(method, path, handler)
+    }
+
+    pub fn try_any<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.router.try_any(path, handler)
+    }
+
+    /// Register a nested router for the app, returning the conflict instead of panicking
+    pub fn try_scope(
+        &mut self,
+        name: &str,
+        scoped_routes: impl Fn(&mut Router),
+    ) -> Result<(), RouteError> {
+        let mut new_router = Router::new();
+
+        scoped_routes(&mut new_router);
+        self.try_use_router(format!("/{}", name).as_ref(), new_router)
+    }
+
+    /// Register the routes served for the requests to the host, returning the invalid host pattern
+    /// instead of panicking. The routes are added into the same router if the host is registered
+    pub fn try_host(
+        &mut self,
+        host: &str,
+        host_routes: impl Fn(&mut Router),
+    ) -> Result<(), RouteError> {
+        let pattern = HostPattern::parse(host)?;
+
+        if let Some((_, router)) = self
+            .hosts
+            .iter_mut()
+            .find(|(existing, _)| existing.as_str() == pattern.as_str())
+        {
+            host_routes(router);
+            return Ok(());
+        }
+
+        let mut router = Router::new();
+        host_routes(&mut router);
+
+        // Static hosts are matched before the host patterns with params
+        let index = if pattern.is_static() {
+            self.hosts
+                .iter()
+                .position(|(existing, _)| !existing.is_static())
+                .unwrap_or(self.hosts.len())
+        } else {
+            self.hosts.len()
+        };
+        self.hosts.insert(index, (pattern, router));
+
+        Ok(())
+    }
+
+    pub fn try_use_service_to(
+        &mut self,
+        path: &str,
+        middleware: impl Middleware,
+    ) -> Result<(), RouteError> {
+        self.router.try_use_service_to(path, middleware)
+    }
+
+    pub fn try_use_router(&mut self, path: &str, router: Router) -> Result<(), RouteError> {
+        self.router.try_use_router(path, router)
+    }
+
+    pub fn try_use_static_to(
+        &mut self,
+        virtual_path: &str,
+        dir_path: &str,
+    ) -> Result<(), RouteError> {
+        self.router.try_use_static_to(virtual_path, dir_path)
+    }
+
+    pub fn try_use_static(&mut self, dir_path: &str) -> Result<(), RouteError> {
+        self.router.try_use_static(dir_path)
+    }
+
     /// Set app state. The app state must impl Clone.
     /// The app state will be passed into endpoint handler context dynamic data.
     ///
@@ -140,29 +380,224 @@ where
         self.app_state = Some(app_state);
     }
 
+    /// Set the deadline for draining in-flight requests once a shutdown signal is received.
+    /// Without a deadline, the server waits until every connection is closed.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use obsidian::App;
+    ///
+    /// let mut app: App = App::new();
+    /// app.set_shutdown_timeout(Duration::from_secs(30));
+    /// ```
+    pub fn set_shutdown_timeout(&mut self, timeout: Duration) {
+        self.shutdown_timeout = Some(timeout);
+    }
+
+    /// Set the policy for the requests whose trailing slash differs from the route path,
+    /// `TrailingSlash::Ignore` by default. The policy also applies to the routes of the merged routers and the hosts
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{router::TrailingSlash, App};
+    ///
+    /// let mut app: App = App::new();
+    /// app.set_trailing_slash(TrailingSlash::MovedPermanently);
+    /// ```
+    pub fn set_trailing_slash(&mut self, trailing_slash: TrailingSlash) {
+        self.router.set_trailing_slash(trailing_slash);
+    }
+
+    /// Match the static segments case-insensitively, disabled by default.
+    /// Params keep the case of the request path.
+    /// The setting also applies to the routes of the merged routers and the hosts
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.router.set_case_insensitive(case_insensitive);
+    }
+
+    /// Print the route table in the startup banner, disabled by default
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::App;
+    ///
+    /// let mut app: App = App::new();
+    /// app.set_print_routes(true);
+    /// ```
+    pub fn set_print_routes(&mut self, print_routes: bool) {
+        self.print_routes = print_routes;
+    }
+
     pub async fn listen(self, port: u16) {
-        let app_server: AppServer = AppServer {
-            router: self.router,
+        if let Err(err) = self.listen_with_shutdown(port, future::pending()).await {
+            eprintln!("{} {}", "[error]".red(), err);
+        }
+    }
+
+    /// Serve the app until SIGINT (Ctrl+C) or SIGTERM is received, then shut down gracefully
+    pub async fn listen_with_shutdown_signal(self, port: u16) -> Result<(), ObsidianError> {
+        self.listen_with_shutdown(port, shutdown_signal()).await
+    }
+
+    /// Serve the app until the `signal` future resolves.
+    /// The server then stops accepting new connections and drains the in-flight requests,
+    /// bounded by the deadline from [`App::set_shutdown_timeout`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use obsidian::{context::Context, App};
+    /// use tokio::sync::oneshot;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut app: App = App::new();
+    ///     app.get("/", |ctx: Context| async { ctx.build("Hello World").ok() });
+    ///
+    ///     let (tx, rx) = oneshot::channel::<()>();
+    ///     let server = tokio::spawn(app.listen_with_shutdown(3000, async {
+    ///         rx.await.ok();
+    ///     }));
+    ///
+    ///     tx.send(()).unwrap();
+    ///     server.await.unwrap().unwrap();
+    /// }
+    /// ```
+    pub async fn listen_with_shutdown(
+        self,
+        port: u16,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), ObsidianError> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))?;
+
+        self.serve_with_shutdown(listener, signal).await
+    }
+
+    /// Serve the app on any address, e.g. `0.0.0.0:3000` or `[::1]:3000`
+    ///
+    /// # Example
+    /// ```no_run
+    /// use obsidian::App;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let app: App = App::new();
+    ///
+    ///     app.bind("0.0.0.0:3000").await.unwrap();
+    /// }
+    /// ```
+    pub async fn bind(self, addr: impl ToSocketAddrs) -> Result<(), ObsidianError> {
+        let listener = TcpListener::bind(addr)?;
+
+        self.serve(listener).await
+    }
+
+    /// Serve the app on a listener which is already bound.
+    /// Binding to port 0 lets the OS pick a free port which can be read back from the listener.
+    pub async fn serve(self, listener: TcpListener) -> Result<(), ObsidianError> {
+        self.serve_with_shutdown(listener, future::pending()).await
+    }
+
+    /// Serve the app on a listener which is already bound until the `signal` future resolves.
+    /// See [`App::listen_with_shutdown`] for the shutdown behaviour.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::net::TcpListener;
+    /// use obsidian::App;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let app: App = App::new();
+    ///
+    ///     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    ///     let addr = listener.local_addr().unwrap();
+    ///     println!("Serving at {}", addr);
+    ///
+    ///     app.serve_with_shutdown(listener, async {
+    ///         tokio::signal::ctrl_c().await.ok();
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub async fn serve_with_shutdown(
+        self,
+        listener: TcpListener,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), ObsidianError> {
+        let routes = if self.print_routes {
+            Some(self.router.routes().collect::<Vec<RouteInfo>>())
+        } else {
+            None
         };
+
+        let default_router = &self.router;
+        let hosts = self
+            .hosts
+            .into_iter()
+            .map(|(pattern, mut router)| {
+                router.inherit_middlewares(default_router);
+                router.inherit_settings(default_router);
+                (pattern, router)
+            })
+            .collect();
+
+        // The router is frozen from here on and shared by every connection
+        let app_server = Arc::new(AppServer::new(self.router).with_hosts(hosts));
         let app_state = self.app_state;
+        let shutdown_timeout = self.shutdown_timeout;
 
         let service = make_service_fn(move |_| {
-            let server_clone = app_server.clone();
+            let app_server = app_server.clone();
             let app_state = app_state.clone();
 
             async {
                 Ok::<_, hyper::Error>(service_fn(move |req| {
-                    let route_value = server_clone.router.search_route(req.uri().path());
+                    let app_server = app_server.clone();
+                    let app_state = app_state.clone();
 
-                    AppServer::resolve_endpoint(req, route_value, app_state.clone())
+                    async move { app_server.resolve_endpoint(req, app_state).await }
                 }))
             }
         });
 
-        let addr = ([127, 0, 0, 1], port).into();
-        let server = Server::bind(&addr).serve(service);
+        let server = Server::from_tcp(listener)?.serve(service);
+
+        print_banner(server.local_addr());
+
+        if let Some(routes) = routes {
+            print_route_table(&routes);
+        }
+
+        // Notify the drain deadline once the shutdown signal is fired
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let graceful = server.with_graceful_shutdown(async move {
+            signal.await;
+            let _ = shutdown_tx.send(());
+        });
+
+        match shutdown_timeout {
+            Some(timeout) => {
+                let deadline = async move {
+                    match shutdown_rx.await {
+                        Ok(()) => time::sleep(timeout).await,
+                        Err(_) => future::pending().await,
+                    }
+                };
+
+                tokio::select! {
+                    result = graceful => Ok(result?),
+                    _ = deadline => Err(ObsidianError::ShutdownTimeout(timeout)),
+                }
+            }
+            None => Ok(graceful.await?),
+        }
+    }
+}
 
-        let logo = r#"
+fn print_banner(addr: SocketAddr) {
+    let logo = r#"
 
       .oooooo.   oooooooooo.   .oooooo..o ooooo oooooooooo.   ooooo       .o.       ooooo      ooo 
      d8P'  `Y8b  `888'   `Y8b d8P'    `Y8 `888' `888'   `Y8b  `888'      .888.      `888b.     `8' 
@@ -174,100 +609,335 @@ where
                                                                                                
         "#;
 
-        println!("{}", logo);
+    println!("{}", logo);
 
-        #[cfg(debug_assertions)]
-        println!(
-            " 🚧  {}: dev [{} + {}]",
-            "Mode".green().bold(),
-            "unoptimized".red().bold(),
-            "debuginfo".blue().bold()
-        );
+    #[cfg(debug_assertions)]
+    println!(
+        " 🚧  {}: dev [{} + {}]",
+        "Mode".green().bold(),
+        "unoptimized".red().bold(),
+        "debuginfo".blue().bold()
+    );
 
-        #[cfg(not(debug_assertions))]
-        println!(
-            " 🚀  {}: release [{}]",
-            "Mode".green().bold(),
-            "optimized".green().bold(),
-        );
+    #[cfg(not(debug_assertions))]
+    println!(
+        " 🚀  {}: release [{}]",
+        "Mode".green().bold(),
+        "optimized".green().bold(),
+    );
+
+    println!(
+        " 🔧  {}: {}",
+        "Version".green().bold(),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    println!(" 🎉  {}: http://{}\n", "Served at".green().bold(), addr);
+}
+
+fn print_route_table(routes: &[RouteInfo]) {
+    let headers = ["METHOD", "PATH", "MIDDLEWARES", "NAME"];
+    let rows = routes
+        .iter()
+        .map(|route| {
+            [
+                route.method.to_string(),
+                route.path.clone(),
+                route.middleware_count.to_string(),
+                route.name.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<[String; 4]>>();
+
+    let mut widths = headers.map(str::len);
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
 
+    // Pad before coloring since the escape codes are counted in the width
+    let pad = |cell: &str, width: usize| format!("{:<width$}", cell, width = width);
+
+    println!(" 🗺️  {}:", "Routes".green().bold());
+    println!(
+        "    {}  {}  {}  {}",
+        pad(headers[0], widths[0]).bold(),
+        pad(headers[1], widths[1]).bold(),
+        pad(headers[2], widths[2]).bold(),
+        headers[3].bold(),
+    );
+
+    for row in rows.iter() {
         println!(
-            " 🔧  {}: {}",
-            "Version".green().bold(),
-            env!("CARGO_PKG_VERSION")
+            "    {}  {}  {}  {}",
+            pad(&row[0], widths[0]).blue(),
+            pad(&row[1], widths[1]),
+            pad(&row[2], widths[2]),
+            row[3],
         );
+    }
+
+    println!();
+}
+
+/// Resolve once the process receives SIGINT (Ctrl+C) or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if signal::ctrl_c().await.is_err() {
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(_) => future::pending::<()>().await,
+        }
+    };
 
-        println!(" 🎉  {}: http://{}\n", "Served at".green().bold(), addr);
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
 
-        server.await.map_err(|_| println!("Server error")).unwrap();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
     }
 }
 
-#[derive(Clone)]
 struct AppServer {
-    router: Router,
+    /// Shared with the context of every request for building the urls of named routes
+    router: Arc<Router>,
+    hosts: Vec<(HostPattern, Arc<Router>)>,
 }
 
 impl AppServer {
+    fn new(router: Router) -> Self {
+        AppServer {
+            router: Arc::new(router),
+            hosts: Vec::new(),
+        }
+    }
+
+    fn with_hosts(mut self, hosts: Vec<(HostPattern, Router)>) -> Self {
+        self.hosts = hosts
+            .into_iter()
+            .map(|(pattern, router)| (pattern, Arc::new(router)))
+            .collect();
+
+        self
+    }
+
+    /// Pick the router of the first host matching the request, with the captured host params.
+    /// The default router is picked if no host matches
+    fn resolve_router(&self, req: &Request<Body>) -> (&Arc<Router>, HashMap<String, String>) {
+        if self.hosts.is_empty() {
+            return (&self.router, HashMap::new());
+        }
+
+        if let Some(host) = request_host(req) {
+            let mut params = RouteParams::new();
+
+            for (pattern, router) in self.hosts.iter() {
+                if pattern.match_host(&host, &mut params) {
+                    let params = params
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect();
+
+                    return (router, params);
+                }
+            }
+        }
+
+        (&self.router, HashMap::new())
+    }
+
     pub async fn resolve_endpoint<T>(
+        &self,
         req: Request<Body>,
-        route_value: Option<RouteValueResult>,
         app_state: Option<T>,
     ) -> Result<Response<Body>, hyper::Error>
     where
         T: Send + Sync + 'static,
     {
-        match route_value {
-            Some(route_value) => {
-                let route = match route_value.get_route(req.method()) {
-                    Some(r) => r,
-                    None => return Ok::<_, hyper::Error>(page_not_found()),
-                };
-                let middlewares = route_value.get_middlewares();
-                let params = route_value.get_params();
-                let mut context = Context::new(req, params);
-                let executor = EndpointExecutor::new(&route.handler, middlewares);
+        let (router, host_params) = self.resolve_router(&req);
+
+        let route_value = match router.search_route(req.uri().path()) {
+            Some(route_value) if !route_value.is_empty() => route_value,
+            // Path not found, or without any route registered (e.g. middleware only)
+            route_value => {
+                // Release the borrowed path before the request is handed to the fallback
+                drop(route_value);
+
+                return Ok::<_, hyper::Error>(
+                    self.not_found(router, req, host_params, app_state).await,
+                );
+            }
+        };
+
+        let method = req.method().clone();
+        let mut allow = None;
 
-                if let Some(state) = app_state {
-                    context.add::<T>(state);
+        let options_handler: Option<Arc<dyn Handler>> = match route_value.get_route(&method) {
+            Some(_) => None,
+            None => {
+                let allowed_methods = route_value.get_allowed_methods();
+
+                if method != Method::OPTIONS {
+                    return Ok::<_, hyper::Error>(method_not_allowed(&allowed_methods));
                 }
 
-                let route_result = executor.next(context).await;
-
-                let route_response = match route_result {
-                    Ok(ctx) => {
-                        let mut res = Response::builder();
-                        if let Some(response) = ctx.take_response() {
-                            if let Some(headers) = response.headers() {
-                                if let Some(response_headers) = res.headers_mut() {
-                                    headers.iter().for_each(move |(key, value)| {
-                                        response_headers
-                                            .insert(key, header::HeaderValue::from_static(value));
-                                    });
-                                }
-                            }
-                            res.status(response.status()).body(response.body())
-                        } else {
-                            // No response found
-                            res.status(StatusCode::OK).body(Body::from(""))
-                        }
+                // Answer OPTIONS automatically from the registered methods
+                allow = Some(allow_header_value(&allowed_methods));
+                Some(Arc::new(automatic_options_handler))
+            }
+        };
+        let middlewares = route_value.get_middlewares();
+        let resource = route_value.get_resource();
+        // Path params take precedence over the host params with the same name
+        let mut params = host_params;
+        let raw_params = match route_value.decode_params_into(&mut params) {
+            Ok(raw_params) => raw_params,
+            Err(err) => return Ok::<_, hyper::Error>(bad_request(err)),
+        };
+        let mut context = Context::new(req, params);
+        if let Some(raw_params) = raw_params {
+            context.set_raw_params(raw_params);
+        }
+        context.set_router(router.clone());
+
+        // Guards are checked against the context so that they can read the params
+        let (handler, route_middlewares) = match &options_handler {
+            Some(handler) => (handler, &[][..]),
+            None => match resource.select_route(&method, &context) {
+                Ok(r) => {
+                    if let Some(response) =
+                        trailing_slash_response(router.trailing_slash(), r, context.uri())
+                    {
+                        return Ok::<_, hyper::Error>(response);
+                    }
+
+                    (&r.handler, r.middlewares.as_slice())
+                }
+                Err(status) => return Ok::<_, hyper::Error>(guard_rejected(status)),
+            },
+        };
+        let executor =
+            EndpointExecutor::new(handler, middlewares).with_route_middleware(route_middlewares);
+
+        if let Some(state) = app_state {
+            context.add::<T>(state);
+        }
+
+        let route_result = executor.next(context).await;
+
+        Ok::<_, hyper::Error>(build_response(route_result, &method, allow))
+    }
+
+    /// Run the fallback of the deepest matched prefix with the middlewares of the prefix,
+    /// or respond the plain 404 if no prefix has a fallback
+    async fn not_found<T>(
+        &self,
+        router: &Arc<Router>,
+        req: Request<Body>,
+        host_params: HashMap<String, String>,
+        app_state: Option<T>,
+    ) -> Response<Body>
+    where
+        T: Send + Sync + 'static,
+    {
+        let (handler, middlewares) = match router.search_fallback(req.uri().path()) {
+            Some(fallback) => match fallback.get_fallback() {
+                Some(handler) => (handler, fallback.get_middlewares()),
+                None => return page_not_found(),
+            },
+            None => return page_not_found(),
+        };
+
+        let method = req.method().clone();
+        let mut context = Context::new(req, host_params);
+        context.set_router(router.clone());
+
+        if let Some(state) = app_state {
+            context.add::<T>(state);
+        }
+
+        let route_result = EndpointExecutor::new(handler, middlewares)
+            .next(context)
+            .await;
+
+        build_response(route_result, &method, None)
+    }
+}
+
+/// Convert the result of the endpoint into the response sent to the client
+fn build_response(
+    route_result: ContextResult,
+    method: &Method,
+    allow: Option<String>,
+) -> Response<Body> {
+    let route_response = match route_result {
+        Ok(ctx) => {
+            let mut res = Response::builder();
+            if let Some(allow) = allow {
+                res = res.header(header::ALLOW, allow);
+            }
+            if let Some(response) = ctx.take_response() {
+                if let Some(headers) = response.headers() {
+                    if let Some(response_headers) = res.headers_mut() {
+                        headers.iter().for_each(move |(key, value)| {
+                            response_headers.insert(key, header::HeaderValue::from_static(value));
+                        });
                     }
-                    Err(err) => {
-                        let body = Body::from(err.to_string());
-                        Response::builder()
-                            .status(StatusCode::INTERNAL_SERVER_ERROR)
-                            .body(body)
+                }
+                let status = response.status();
+                let mut body = response.body();
+
+                // HEAD response keeps the Content-Length of the GET response without body
+                if method == Method::HEAD {
+                    if let Some(response_headers) = res.headers_mut() {
+                        if let (false, Some(len)) = (
+                            response_headers.contains_key(header::CONTENT_LENGTH),
+                            body.size_hint().exact(),
+                        ) {
+                            response_headers
+                                .insert(header::CONTENT_LENGTH, header::HeaderValue::from(len));
+                        }
                     }
-                };
+                    body = Body::empty();
+                }
 
-                Ok::<_, hyper::Error>(route_response.unwrap_or_else(|_| {
-                    internal_server_error(ObsidianError::GeneralError(
-                        "Error while constructing response body".to_string(),
-                    ))
-                }))
+                res.status(status).body(body)
+            } else {
+                // No response found
+                res.status(StatusCode::OK).body(Body::from(""))
             }
-            _ => Ok::<_, hyper::Error>(page_not_found()),
         }
+        Err(err) => {
+            let body = Body::from(err.to_string());
+            Response::builder().status(error_status(&err)).body(body)
+        }
+    };
+
+    route_response.unwrap_or_else(|_| {
+        internal_server_error(ObsidianError::GeneralError(
+            "Error while constructing response body".to_string(),
+        ))
+    })
+}
+
+/// Status of the error returned by the endpoint, the exceeded body limits are answered with 413
+fn error_status(err: &ObsidianError) -> StatusCode {
+    match err {
+        ObsidianError::MultipartError(MultipartError::FieldTooLarge { .. })
+        | ObsidianError::MultipartError(MultipartError::BodyTooLarge { .. }) => {
+            StatusCode::PAYLOAD_TOO_LARGE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
@@ -278,34 +948,157 @@ fn page_not_found() -> Response<Body> {
     server_response
 }
 
-fn internal_server_error(err: impl std::error::Error) -> Response<Body> {
-    let body = Body::from(err.to_string());
-    Response::builder()
-        .status(StatusCode::INTERNAL_SERVER_ERROR)
-        .body(body)
-        .unwrap()
-}
+/// Lowercase host of the request without port, from the uri authority or the `Host` header
+fn request_host(req: &Request<Body>) -> Option<String> {
+    let authority = match req.uri().authority() {
+        Some(authority) => authority.clone(),
+        None => req
+            .headers()
+            .get(header::HOST)?
+            .to_str()
+            .ok()?
+            .parse::<Authority>()
+            .ok()?,
+    };
 
-pub struct EndpointExecutor<'a> {
-    pub route_endpoint: &'a Arc<dyn Handler>,
-    pub middleware: &'a [Arc<dyn Middleware>],
+    Some(authority.host().trim_end_matches('.').to_ascii_lowercase())
 }
 
-impl<'a> EndpointExecutor<'a> {
-    pub fn new(
-        route_endpoint: &'a Arc<dyn Handler>,
-        middleware: &'a [Arc<dyn Middleware>],
-    ) -> Self {
-        EndpointExecutor {
-            route_endpoint,
-            middleware,
-        }
+/// Response for the request path whose trailing slash differs from the route path,
+/// None if the request should be served by the route
+fn trailing_slash_response(
+    policy: TrailingSlash,
+    route: &Route,
+    uri: &Uri,
+) -> Option<Response<Body>> {
+    let trailing_slash = route.trailing_slash?;
+    let path = uri.path();
+
+    if policy == TrailingSlash::Ignore || path.len() <= 1 || path.ends_with('/') == trailing_slash {
+        return None;
+    }
+
+    let status = match policy.redirect_status() {
+        Some(status) => status,
+        None => return Some(page_not_found()),
+    };
+
+    // Runs of '/' are collapsed, so that the location never starts with "//" read as another host
+    let mut location = String::with_capacity(path.len());
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        location.push('/');
+        location.push_str(segment);
+    }
+    if trailing_slash || location.is_empty() {
+        location.push('/');
+    }
+    if let Some(query) = uri.query() {
+        location.push('?');
+        location.push_str(query);
+    }
+
+    let response = Response::builder()
+        .status(status)
+        .header(header::LOCATION, location)
+        .body(Body::from(""))
+        .unwrap_or_else(|_| {
+            internal_server_error(ObsidianError::GeneralError(
+                "Error while constructing response body".to_string(),
+            ))
+        });
+
+    Some(response)
+}
+
+fn guard_rejected(status: StatusCode) -> Response<Body> {
+    let body = format!(
+        "{} {}",
+        status.as_str(),
+        status.canonical_reason().unwrap_or_default()
+    );
+
+    Response::builder()
+        .status(status)
+        .body(Body::from(body))
+        .unwrap_or_else(|_| {
+            internal_server_error(ObsidianError::GeneralError(
+                "Error while constructing response body".to_string(),
+            ))
+        })
+}
+
+fn method_not_allowed(methods: &[Method]) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::METHOD_NOT_ALLOWED)
+        .header(header::ALLOW, allow_header_value(methods))
+        .body(Body::from("405 Method Not Allowed"))
+        .unwrap_or_else(|_| {
+            internal_server_error(ObsidianError::GeneralError(
+                "Error while constructing response body".to_string(),
+            ))
+        })
+}
+
+fn allow_header_value(methods: &[Method]) -> String {
+    methods
+        .iter()
+        .map(|method| method.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+async fn automatic_options_handler(ctx: Context) -> ContextResult {
+    ctx.build(StatusCode::NO_CONTENT).ok()
+}
+
+fn bad_request(err: impl std::error::Error) -> Response<Body> {
+    let body = Body::from(err.to_string());
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .body(body)
+        .unwrap()
+}
+
+fn internal_server_error(err: impl std::error::Error) -> Response<Body> {
+    let body = Body::from(err.to_string());
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(body)
+        .unwrap()
+}
+
+pub struct EndpointExecutor<'a> {
+    pub route_endpoint: &'a Arc<dyn Handler>,
+    pub middleware: &'a [Arc<dyn Middleware>],
+    /// Middlewares of the route, executed after the middlewares of the path
+    pub route_middleware: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> EndpointExecutor<'a> {
+    pub fn new(
+        route_endpoint: &'a Arc<dyn Handler>,
+        middleware: &'a [Arc<dyn Middleware>],
+    ) -> Self {
+        EndpointExecutor {
+            route_endpoint,
+            middleware,
+            route_middleware: &[],
+        }
+    }
+
+    /// Set the middlewares of the route, which are executed after the middlewares of the path
+    pub fn with_route_middleware(mut self, route_middleware: &'a [Arc<dyn Middleware>]) -> Self {
+        self.route_middleware = route_middleware;
+        self
     }
 
     pub async fn next(mut self, context: Context) -> ContextResult {
         if let Some((current, all_next)) = self.middleware.split_first() {
             self.middleware = all_next;
             current.handle(context, self).await
+        } else if let Some((current, all_next)) = self.route_middleware.split_first() {
+            self.route_middleware = all_next;
+            current.handle(context, self).await
         } else {
             self.route_endpoint.call(context).await
         }
@@ -316,6 +1109,8 @@ impl<'a> EndpointExecutor<'a> {
 mod test {
     use super::*;
     use crate::context::Context;
+    use crate::router::extract::{Json, Params, State};
+    use crate::router::guard;
     use async_std::task;
     use hyper::{body, body::Buf, StatusCode};
 
@@ -339,7 +1134,7 @@ mod test {
                 ctx.build("test_app_server").ok()
             });
 
-            let app_server = AppServer { router };
+            let app_server = AppServer::new(router);
 
             let req_builder = Request::builder();
 
@@ -348,11 +1143,10 @@ mod test {
                 .body(Body::from("test_app_server"))
                 .unwrap();
 
-            let route_value = app_server.router.search_route(req.uri().path());
-            let actual_response =
-                AppServer::resolve_endpoint::<DefaultAppState>(req, route_value, None)
-                    .await
-                    .unwrap();
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
 
             let mut expected_response = Response::new(Body::from("test_app_server"));
             *expected_response.status_mut() = StatusCode::OK;
@@ -372,4 +1166,1168 @@ mod test {
             assert_eq!(actual_res_body.unwrap(), expected_res_body.unwrap());
         })
     }
+
+    #[test]
+    fn test_app_server_method_not_allowed() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/test", |ctx: Context| async move { ctx.build("get").ok() });
+            router.put("/test", |ctx: Context| async move { ctx.build("put").ok() });
+            router.delete(
+                "/test",
+                |ctx: Context| async move { ctx.build("delete").ok() },
+            );
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri("/test")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(
+                actual_response.headers().get(header::ALLOW).unwrap(),
+                "DELETE, GET, HEAD, OPTIONS, PUT"
+            );
+        })
+    }
+
+    #[test]
+    fn test_app_server_middleware_only_path_not_found() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.use_service(Logger::new());
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder().uri("/").body(Body::from("")).unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::NOT_FOUND);
+            assert!(actual_response.headers().get(header::ALLOW).is_none());
+        })
+    }
+
+    #[test]
+    fn test_app_server_automatic_head() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/test", |ctx: Context| async move {
+                ctx.build("test_app_server").ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .method(Method::HEAD)
+                .uri("/test")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+            assert_eq!(
+                actual_response
+                    .headers()
+                    .get(header::CONTENT_LENGTH)
+                    .unwrap(),
+                "15"
+            );
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert!(actual_res_body.is_empty());
+        })
+    }
+
+    #[test]
+    fn test_app_server_explicit_head() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/test", |ctx: Context| async move { ctx.build("get").ok() });
+            router.head("/test", |ctx: Context| async move {
+                ctx.build(StatusCode::ACCEPTED).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .method(Method::HEAD)
+                .uri("/test")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::ACCEPTED);
+        })
+    }
+
+    #[test]
+    fn test_app_server_automatic_options() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/test", |ctx: Context| async move { ctx.build("get").ok() });
+            router.post(
+                "/test",
+                |ctx: Context| async move { ctx.build("post").ok() },
+            );
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/test")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::NO_CONTENT);
+            assert_eq!(
+                actual_response.headers().get(header::ALLOW).unwrap(),
+                "GET, HEAD, OPTIONS, POST"
+            );
+        })
+    }
+
+    #[test]
+    fn test_app_server_explicit_options() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/test", |ctx: Context| async move { ctx.build("get").ok() });
+            router.options("/test", |ctx: Context| async move {
+                ctx.build("custom options").ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/test")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+            assert!(actual_response.headers().get(header::ALLOW).is_none());
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "custom options");
+        })
+    }
+
+    #[test]
+    fn test_app_server_any_route_only() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.any("/x", |ctx: Context| async move {
+                let method = ctx.method().to_string();
+                ctx.build(method).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            for method in [Method::GET, Method::POST, Method::PUT, Method::OPTIONS].iter() {
+                let actual_response = send_request(&app_server, method.clone(), "/x").await;
+
+                assert_eq!(actual_response.status(), StatusCode::OK);
+
+                let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+                assert_eq!(actual_res_body, method.as_str());
+            }
+        })
+    }
+
+    #[test]
+    fn test_app_server_params() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/users/:id/:mode", |ctx: Context| async move {
+                let id: i32 = ctx.param("id")?;
+                let mode: String = ctx.param("mode")?;
+
+                ctx.build(format!("{} {}", id, mode)).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .uri("/users/1/edit")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "1 edit");
+        })
+    }
+
+    #[test]
+    fn test_app_server_wildcard_param() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/files/:owner/*path", |ctx: Context| async move {
+                let owner: String = ctx.param("owner")?;
+                let path: String = ctx.param("path")?;
+
+                ctx.build(format!("{} {}", owner, path)).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .uri("/files/alice/docs/guide/intro.md")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "alice docs/guide/intro.md");
+        })
+    }
+
+    #[test]
+    fn test_app_server_param_constraint() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/users/:id<int>", |ctx: Context| async move {
+                let id: i32 = ctx.param("id")?;
+
+                ctx.build(format!("{}", id)).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .uri("/users/abc")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::NOT_FOUND);
+
+            let req = Request::builder()
+                .uri("/users/12")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "12");
+        })
+    }
+
+    async fn send_request(app_server: &AppServer, method: Method, uri: &str) -> Response<Body> {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::from(""))
+            .unwrap();
+
+        app_server
+            .resolve_endpoint::<DefaultAppState>(req, None)
+            .await
+            .unwrap()
+    }
+
+    fn trailing_slash_router(trailing_slash: TrailingSlash) -> Router {
+        let mut router = Router::new();
+        let mut sub_router = Router::new();
+
+        router.get(
+            "/users",
+            |ctx: Context| async move { ctx.build("users").ok() },
+        );
+        router.post(
+            "/posts/",
+            |ctx: Context| async move { ctx.build("posts").ok() },
+        );
+        router.get("/static/*path", |ctx: Context| async move {
+            ctx.build("static").ok()
+        });
+
+        sub_router.get("/", |ctx: Context| async move { ctx.build("admin").ok() });
+
+        router.use_router("/admin", sub_router);
+        router.set_trailing_slash(trailing_slash);
+
+        router
+    }
+
+    #[test]
+    fn test_app_server_trailing_slash_ignore() {
+        task::block_on(async {
+            let app_server = AppServer::new(trailing_slash_router(TrailingSlash::Ignore));
+
+            for (method, uri) in [
+                (Method::GET, "/users"),
+                (Method::GET, "/users/"),
+                (Method::POST, "/posts"),
+                (Method::POST, "/posts/"),
+                (Method::GET, "/admin/"),
+            ] {
+                let actual_response = send_request(&app_server, method, uri).await;
+
+                assert_eq!(actual_response.status(), StatusCode::OK);
+            }
+        })
+    }
+
+    #[test]
+    fn test_app_server_trailing_slash_strict() {
+        task::block_on(async {
+            let app_server = AppServer::new(trailing_slash_router(TrailingSlash::Strict));
+
+            for (method, uri, status) in [
+                (Method::GET, "/users", StatusCode::OK),
+                (Method::GET, "/users/", StatusCode::NOT_FOUND),
+                (Method::POST, "/posts/", StatusCode::OK),
+                (Method::POST, "/posts", StatusCode::NOT_FOUND),
+                (Method::GET, "/admin", StatusCode::OK),
+                (Method::GET, "/admin/", StatusCode::NOT_FOUND),
+                (Method::GET, "/static/css/", StatusCode::OK),
+                (Method::GET, "/", StatusCode::NOT_FOUND),
+            ] {
+                let actual_response = send_request(&app_server, method, uri).await;
+
+                assert_eq!(actual_response.status(), status, "{}", uri);
+            }
+        })
+    }
+
+    #[test]
+    fn test_app_server_trailing_slash_redirect() {
+        task::block_on(async {
+            let app_server = AppServer::new(trailing_slash_router(TrailingSlash::MovedPermanently));
+
+            let actual_response = send_request(&app_server, Method::GET, "/users/?page=2").await;
+
+            assert_eq!(actual_response.status(), StatusCode::MOVED_PERMANENTLY);
+            assert_eq!(
+                actual_response.headers().get(header::LOCATION).unwrap(),
+                "/users?page=2"
+            );
+
+            let actual_response = send_request(&app_server, Method::GET, "/admin/").await;
+
+            assert_eq!(actual_response.status(), StatusCode::MOVED_PERMANENTLY);
+            assert_eq!(
+                actual_response.headers().get(header::LOCATION).unwrap(),
+                "/admin"
+            );
+
+            let app_server =
+                AppServer::new(trailing_slash_router(TrailingSlash::PermanentRedirect));
+
+            let actual_response = send_request(&app_server, Method::POST, "/posts").await;
+
+            assert_eq!(actual_response.status(), StatusCode::PERMANENT_REDIRECT);
+            assert_eq!(
+                actual_response.headers().get(header::LOCATION).unwrap(),
+                "/posts/"
+            );
+
+            let actual_response = send_request(&app_server, Method::GET, "/users").await;
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+        })
+    }
+
+    #[test]
+    fn test_app_server_trailing_slash_redirect_same_host() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get(
+                "/:slug",
+                |ctx: Context| async move { ctx.build("slug").ok() },
+            );
+            router.set_trailing_slash(TrailingSlash::MovedPermanently);
+
+            let app_server = AppServer::new(router);
+
+            for (uri, location) in [
+                ("//evil.com/", "/evil.com"),
+                ("///evil.com//", "/evil.com"),
+                ("/page//", "/page"),
+            ] {
+                let actual_response = send_request(&app_server, Method::GET, uri).await;
+
+                assert_eq!(
+                    actual_response.status(),
+                    StatusCode::MOVED_PERMANENTLY,
+                    "{}",
+                    uri
+                );
+                assert_eq!(
+                    actual_response.headers().get(header::LOCATION).unwrap(),
+                    location
+                );
+            }
+        })
+    }
+
+    #[test]
+    fn test_app_server_url_for_round_trip() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router
+                .get("/caf%C3%A9/:name", |ctx: Context| async move {
+                    let name: String = ctx.param("name")?;
+
+                    ctx.build(name).ok()
+                })
+                .name("cafe");
+
+            let url = router.url_for("cafe", &[("name", "a b")]).unwrap();
+            let app_server = AppServer::new(router);
+
+            let actual_response = send_request(&app_server, Method::GET, &url).await;
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "a b");
+        })
+    }
+
+    #[test]
+    fn test_app_server_multipart_limit() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.post("/profile", |mut ctx: Context| async move {
+                let form: HashMap<String, String> = ctx.multipart()?.form().await?;
+
+                ctx.build(form.len().to_string()).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            for (size, status) in [
+                (16, StatusCode::OK),
+                // Larger than the default field limit
+                (2 * 1024 * 1024, StatusCode::PAYLOAD_TOO_LARGE),
+            ] {
+                let body = format!(
+                    "--boundary\r\nContent-Disposition: form-data; name=\"bio\"\r\n\r\n{}\r\n--boundary--\r\n",
+                    "a".repeat(size)
+                );
+                let req = Request::builder()
+                    .method(Method::POST)
+                    .uri("/profile")
+                    .header("Content-Type", "multipart/form-data; boundary=boundary")
+                    .body(Body::from(body))
+                    .unwrap();
+
+                let actual_response = app_server
+                    .resolve_endpoint::<DefaultAppState>(req, None)
+                    .await
+                    .unwrap();
+
+                assert_eq!(actual_response.status(), status);
+            }
+        })
+    }
+
+    #[test]
+    fn test_app_server_decode_params() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.get("/files/:name", |ctx: Context| async move {
+                let name: String = ctx.param("name")?;
+                let raw_name = ctx.raw_param("name").unwrap_or_default().to_string();
+
+                ctx.build(format!("{} {}", name, raw_name)).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let actual_response = send_request(&app_server, Method::GET, "/files/my%20doc").await;
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "my doc my%20doc");
+
+            let actual_response = send_request(&app_server, Method::GET, "/files/%FF").await;
+
+            assert_eq!(actual_response.status(), StatusCode::BAD_REQUEST);
+        })
+    }
+
+    async fn send_host_request(app_server: &AppServer, host: &str, uri: &str) -> String {
+        let req = Request::builder()
+            .uri(uri)
+            .header(header::HOST, host)
+            .body(Body::from(""))
+            .unwrap();
+
+        let actual_response = app_server
+            .resolve_endpoint::<DefaultAppState>(req, None)
+            .await
+            .unwrap();
+
+        assert_eq!(actual_response.status(), StatusCode::OK);
+
+        let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+        String::from_utf8(actual_res_body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_app_server_host() {
+        task::block_on(async {
+            let mut app: App = App::new();
+
+            app.get("/", |ctx: Context| async move { ctx.build("default").ok() });
+            app.host(":tenant.example.com", |router| {
+                router.get("/users/:id", |ctx: Context| async move {
+                    let tenant: String = ctx.param("tenant")?;
+                    let id: String = ctx.param("id")?;
+
+                    ctx.build(format!("{} {}", tenant, id)).ok()
+                });
+                router.get("/:tenant", |ctx: Context| async move {
+                    let tenant: String = ctx.param("tenant")?;
+
+                    ctx.build(tenant).ok()
+                });
+            });
+            app.host("api.example.com", |router| {
+                router.get("/", |ctx: Context| async move { ctx.build("api").ok() });
+            });
+
+            assert_eq!(
+                app.hosts
+                    .iter()
+                    .map(|(pattern, _)| pattern.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["api.example.com", ":tenant.example.com"]
+            );
+            assert_eq!(
+                app.try_host("api..example.com", |_| {}),
+                Err(RouteError::InvalidHost {
+                    host: "api..example.com".to_string()
+                })
+            );
+
+            let app_server = AppServer::new(app.router).with_hosts(app.hosts);
+
+            assert_eq!(
+                send_host_request(&app_server, "api.example.com", "/").await,
+                "api"
+            );
+            assert_eq!(
+                send_host_request(&app_server, "API.Example.com:8080", "/").await,
+                "api"
+            );
+            assert_eq!(
+                send_host_request(&app_server, "acme.example.com", "/users/1").await,
+                "acme 1"
+            );
+            assert_eq!(
+                send_host_request(&app_server, "acme.example.com", "/path").await,
+                "path"
+            );
+            assert_eq!(
+                send_host_request(&app_server, "example.com", "/").await,
+                "default"
+            );
+            assert_eq!(
+                send_host_request(&app_server, "other.example.com", "http://api.example.com/")
+                    .await,
+                "api"
+            );
+        })
+    }
+
+    #[test]
+    fn test_app_server_guard() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router
+                .post(
+                    "/users",
+                    |ctx: Context| async move { ctx.build("json").ok() },
+                )
+                .guard(guard::content_type("application/json"));
+            router
+                .post(
+                    "/users",
+                    |ctx: Context| async move { ctx.build("form").ok() },
+                )
+                .guard(guard::content_type("application/x-www-form-urlencoded"));
+            router
+                .get("/users", |ctx: Context| async move { ctx.build("v2").ok() })
+                .guard(guard::header("X-API-Version", "2"));
+            router
+                .get(
+                    "/users",
+                    |ctx: Context| async move { ctx.build("query").ok() },
+                )
+                .guard(|ctx: &Context| ctx.uri().query().is_some());
+            router
+                .get("/posts", |ctx: Context| async move { ctx.build("v2").ok() })
+                .guard(guard::header("X-API-Version", "2"));
+
+            let app_server = AppServer::new(router);
+
+            let test_cases = [
+                (
+                    Method::POST,
+                    "/users",
+                    "Content-Type",
+                    "application/json",
+                    StatusCode::OK,
+                    "json",
+                ),
+                (
+                    Method::POST,
+                    "/users",
+                    "Content-Type",
+                    "application/x-www-form-urlencoded",
+                    StatusCode::OK,
+                    "form",
+                ),
+                (
+                    Method::POST,
+                    "/users",
+                    "Content-Type",
+                    "text/plain",
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "415 Unsupported Media Type",
+                ),
+                (
+                    Method::GET,
+                    "/users",
+                    "X-API-Version",
+                    "2",
+                    StatusCode::OK,
+                    "v2",
+                ),
+                (
+                    Method::GET,
+                    "/users?page=1",
+                    "X-API-Version",
+                    "1",
+                    StatusCode::OK,
+                    "query",
+                ),
+                (
+                    Method::GET,
+                    "/users",
+                    "X-API-Version",
+                    "1",
+                    StatusCode::NOT_FOUND,
+                    "404 Not Found",
+                ),
+                (
+                    Method::HEAD,
+                    "/posts",
+                    "X-API-Version",
+                    "2",
+                    StatusCode::OK,
+                    "",
+                ),
+                (
+                    Method::GET,
+                    "/posts",
+                    "X-API-Version",
+                    "1",
+                    StatusCode::NOT_FOUND,
+                    "404 Not Found",
+                ),
+            ];
+
+            for (method, uri, header_name, header_value, status, expected_body) in test_cases {
+                let req = Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(header_name, header_value)
+                    .body(Body::from(""))
+                    .unwrap();
+
+                let actual_response = app_server
+                    .resolve_endpoint::<DefaultAppState>(req, None)
+                    .await
+                    .unwrap();
+
+                assert_eq!(actual_response.status(), status, "{} {}", uri, header_value);
+
+                let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+                assert_eq!(actual_res_body, expected_body);
+            }
+        })
+    }
+
+    struct Tag(&'static str);
+
+    #[async_trait::async_trait]
+    impl Middleware for Tag {
+        async fn handle<'a>(
+            &'a self,
+            mut context: Context,
+            ep_executor: EndpointExecutor<'a>,
+        ) -> ContextResult {
+            context.add(Tag(self.0));
+            ep_executor.next(context).await
+        }
+    }
+
+    #[test]
+    fn test_app_server_fallback() {
+        task::block_on(async {
+            let mut router = Router::new();
+            let mut api_router = Router::new();
+
+            router.get(
+                "/about",
+                |ctx: Context| async move { ctx.build("about").ok() },
+            );
+            router.use_service_to("/static", Tag("static"));
+            router.fallback(|ctx: Context| async move { ctx.build("index.html").ok() });
+
+            api_router.use_service(Tag("api"));
+            api_router.get(
+                "/users",
+                |ctx: Context| async move { ctx.build("users").ok() },
+            );
+            api_router.fallback(|ctx: Context| async move {
+                let tag = ctx.get::<Tag>().map(|tag| tag.0).unwrap_or_default();
+
+                ctx.build(format!("{} not found", tag))
+                    .with_status(StatusCode::NOT_FOUND)
+                    .ok()
+            });
+
+            router.use_router("/api", api_router);
+
+            let app_server = AppServer::new(router);
+
+            let test_cases = [
+                ("/about", StatusCode::OK, "about"),
+                ("/static", StatusCode::OK, "index.html"),
+                ("/dashboard/settings", StatusCode::OK, "index.html"),
+                ("/api/users", StatusCode::OK, "users"),
+                ("/api/posts", StatusCode::NOT_FOUND, "api not found"),
+                ("/api", StatusCode::NOT_FOUND, "api not found"),
+            ];
+
+            for (uri, status, expected_body) in test_cases {
+                let actual_response = send_request(&app_server, Method::GET, uri).await;
+
+                assert_eq!(actual_response.status(), status, "{}", uri);
+
+                let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+                assert_eq!(actual_res_body, expected_body);
+            }
+
+            let app_server = AppServer::new(Router::new());
+            let actual_response = send_request(&app_server, Method::GET, "/missing").await;
+
+            assert_eq!(actual_response.status(), StatusCode::NOT_FOUND);
+        })
+    }
+
+    struct RequireHeader(&'static str);
+
+    #[async_trait::async_trait]
+    impl Middleware for RequireHeader {
+        async fn handle<'a>(
+            &'a self,
+            context: Context,
+            ep_executor: EndpointExecutor<'a>,
+        ) -> ContextResult {
+            if context.headers().contains_key(self.0) {
+                ep_executor.next(context).await
+            } else {
+                context
+                    .build("401 Unauthorized")
+                    .with_status(StatusCode::UNAUTHORIZED)
+                    .ok()
+            }
+        }
+    }
+
+    #[test]
+    fn test_app_server_route_middleware() {
+        task::block_on(async {
+            let mut app: App = App::new();
+
+            app.scope("admin", |router| {
+                router.use_service(Tag("scope"));
+                router.use_service(RequireHeader("x-admin"));
+
+                router.get("/posts", |ctx: Context| async move {
+                    let tag = ctx.get::<Tag>().map(|tag| tag.0).unwrap_or_default();
+
+                    ctx.build(tag).ok()
+                });
+                router
+                    .post("/posts", |ctx: Context| async move {
+                        let tag = ctx.get::<Tag>().map(|tag| tag.0).unwrap_or_default();
+
+                        ctx.build(tag).ok()
+                    })
+                    .with(RequireHeader("authorization"))
+                    .with(Tag("route"));
+            });
+
+            let app_server = AppServer::new(app.router);
+
+            let test_cases = [
+                (Method::GET, vec!["x-admin"], StatusCode::OK, "scope"),
+                (
+                    Method::GET,
+                    vec![],
+                    StatusCode::UNAUTHORIZED,
+                    "401 Unauthorized",
+                ),
+                (
+                    Method::POST,
+                    vec!["x-admin"],
+                    StatusCode::UNAUTHORIZED,
+                    "401 Unauthorized",
+                ),
+                (
+                    Method::POST,
+                    vec!["x-admin", "authorization"],
+                    StatusCode::OK,
+                    "route",
+                ),
+            ];
+
+            for (method, headers, status, expected_body) in test_cases {
+                let mut req = Request::builder().method(method).uri("/admin/posts");
+
+                for header in headers {
+                    req = req.header(header, "1");
+                }
+
+                let actual_response = app_server
+                    .resolve_endpoint::<DefaultAppState>(req.body(Body::from("")).unwrap(), None)
+                    .await
+                    .unwrap();
+
+                assert_eq!(actual_response.status(), status);
+
+                let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+                assert_eq!(actual_res_body, expected_body);
+            }
+        })
+    }
+
+    #[test]
+    fn test_app_server_url_for() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router
+                .get("/users/:id", |ctx: Context| async move {
+                    ctx.build("user").ok()
+                })
+                .name("user");
+            router.get("/redirect/:id", |ctx: Context| async move {
+                let id: String = ctx.param("id")?;
+                let url = ctx.url_for("user", &[("id", &id)])?;
+
+                ctx.build(url).ok()
+            });
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .uri("/redirect/12")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "/users/12");
+        })
+    }
+
+    #[test]
+    fn test_app_server_route_table() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router
+                .get("/users/:id", |ctx: Context| async move {
+                    ctx.build("user").ok()
+                })
+                .name("user");
+            router.get("/admin/routes", Router::route_table);
+
+            let app_server = AppServer::new(router);
+
+            let req = Request::builder()
+                .uri("/admin/routes")
+                .body(Body::from(""))
+                .unwrap();
+
+            let actual_response = app_server
+                .resolve_endpoint::<DefaultAppState>(req, None)
+                .await
+                .unwrap();
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            let actual_routes: serde_json::Value =
+                serde_json::from_slice(&actual_res_body).unwrap();
+
+            assert_eq!(
+                actual_routes,
+                serde_json::json!([
+                    {"method": "GET", "path": "/users/:id", "middleware_count": 0, "name": "user"},
+                    {"method": "GET", "path": "/admin/routes", "middleware_count": 0, "name": null},
+                ])
+            );
+        })
+    }
+
+    #[test]
+    fn test_app_server_extractor() {
+        task::block_on(async {
+            #[derive(Clone)]
+            struct AppState {
+                greeting: String,
+            }
+
+            #[derive(serde::Deserialize)]
+            struct UserParams {
+                id: i32,
+            }
+
+            #[derive(serde::Deserialize)]
+            struct User {
+                name: String,
+            }
+
+            let mut router = Router::new();
+
+            router.put(
+                "/users/:id",
+                |Params(params): Params<UserParams>,
+                 Json(user): Json<User>,
+                 State(state): State<AppState>,
+                 method: Method| async move {
+                    format!("{} {} {} {}", method, state.greeting, params.id, user.name)
+                },
+            );
+
+            router.get("/greeting", |State(state): State<AppState>| async move {
+                state.greeting
+            });
+
+            let app_server = AppServer::new(router);
+            let app_state = AppState {
+                greeting: "hello".to_string(),
+            };
+
+            let test_cases = [
+                (
+                    "/users/1",
+                    "{\"name\":\"obsidian\"}",
+                    StatusCode::OK,
+                    "PUT hello 1 obsidian",
+                ),
+                (
+                    "/users/abc",
+                    "{\"name\":\"obsidian\"}",
+                    StatusCode::BAD_REQUEST,
+                    "Failed to parse id: invalid digit found in string",
+                ),
+                (
+                    "/users/1",
+                    "{\"id\":1}",
+                    StatusCode::BAD_REQUEST,
+                    "missing field `name` at line 1 column 8",
+                ),
+            ];
+
+            for (uri, json, expected_status, expected_body) in test_cases.iter() {
+                let req = Request::builder()
+                    .method(Method::PUT)
+                    .uri(*uri)
+                    .body(Body::from(*json))
+                    .unwrap();
+
+                let actual_response = app_server
+                    .resolve_endpoint(req, Some(app_state.clone()))
+                    .await
+                    .unwrap();
+
+                assert_eq!(actual_response.status(), *expected_status);
+
+                let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+                assert_eq!(actual_res_body, *expected_body);
+            }
+
+            // Missing app state is the mistake of the server
+            let actual_response = send_request(&app_server, Method::GET, "/greeting").await;
+
+            assert_eq!(actual_response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "Internal Server Error");
+        })
+    }
+
+    #[test]
+    fn test_app_server_responder_handler() {
+        task::block_on(async {
+            async fn get_user(ctx: Context) -> Result<String, ObsidianError> {
+                let id: i32 = ctx.param("id")?;
+
+                Ok(format!("user {}", id))
+            }
+
+            let mut router = Router::new();
+
+            router.get("/users/:id", get_user);
+            router.get("/posts/:id", |ctx: Context| async move {
+                match ctx.param::<i32>("id") {
+                    Ok(1) => Some("post 1"),
+                    _ => None,
+                }
+            });
+            router.get("/created", |_ctx: Context| async { StatusCode::CREATED });
+
+            let app_server = AppServer::new(router);
+
+            let test_cases = [
+                ("/users/1", StatusCode::OK, "user 1"),
+                (
+                    "/users/abc",
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal Server Error",
+                ),
+                ("/posts/1", StatusCode::OK, "post 1"),
+                ("/posts/2", StatusCode::NOT_FOUND, "Not Found"),
+                ("/created", StatusCode::CREATED, ""),
+            ];
+
+            for (uri, expected_status, expected_body) in test_cases.iter() {
+                let actual_response = send_request(&app_server, Method::GET, uri).await;
+
+                assert_eq!(actual_response.status(), *expected_status);
+
+                let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+                assert_eq!(actual_res_body, *expected_body);
+            }
+        })
+    }
+
+    struct RequireTag;
+
+    #[async_trait::async_trait]
+    impl Middleware for RequireTag {
+        async fn handle<'a>(
+            &'a self,
+            mut context: Context,
+            ep_executor: EndpointExecutor<'a>,
+        ) -> ContextResult {
+            context.add(Tag("middleware"));
+            let context = ep_executor.next(context).await?;
+
+            match context.get::<Tag>() {
+                Some(Tag("middleware")) => Ok(context),
+                _ => context
+                    .build((StatusCode::INTERNAL_SERVER_ERROR, "Tag dropped"))
+                    .ok(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_app_server_responder_handler_keeps_extensions() {
+        task::block_on(async {
+            let mut router = Router::new();
+
+            router.use_service(RequireTag);
+            router.get("/tag", |ctx: Context| async move {
+                // The context is kept by the function after the response is returned
+                async_std::task::spawn(async move { drop(ctx) });
+
+                "tag kept"
+            });
+
+            let app_server = AppServer::new(router);
+
+            let actual_response = send_request(&app_server, Method::GET, "/tag").await;
+
+            assert_eq!(actual_response.status(), StatusCode::OK);
+
+            let actual_res_body = body::to_bytes(actual_response).await.unwrap();
+            assert_eq!(actual_res_body, "tag kept");
+        })
+    }
 }
@@ -1,3 +1,5 @@
+pub mod multipart;
+
 use http::Extensions;
 use hyper::{body, body::Buf};
 use serde::de::DeserializeOwned;
@@ -8,11 +10,17 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::From;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use crate::router::{from_cow_map, ContextResult, Responder, Response};
+use self::multipart::Multipart;
+use crate::error::MultipartError;
+use crate::router::{
+    from_cow_map, from_json_with_params, from_params_map, ContextResult, Responder, Response,
+    Router,
+};
 use crate::ObsidianError;
 use crate::{
-    header::{HeaderName, HeaderValue},
+    header::{self, HeaderName, HeaderValue},
     Body, HeaderMap, Method, Request, StatusCode, Uri,
 };
 
@@ -22,7 +30,10 @@ use crate::{
 pub struct Context {
     request: Request<Body>,
     params_data: HashMap<String, String>,
+    /// Params before percent-decoding, only the ones changed by decoding
+    raw_params_data: Option<HashMap<String, String>>,
     response: Option<Response>,
+    router: Option<Arc<Router>>,
 }
 
 impl Context {
@@ -30,10 +41,41 @@ impl Context {
         Self {
             request,
             params_data,
+            raw_params_data: None,
             response: None,
+            router: None,
         }
     }
 
+    /// Split a context with a copy of the request head, params and router, taking the extensions over.
+    /// It carries the response and the extensions while the handler function consumes this context
+    pub(crate) fn split_head(&mut self) -> Self {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = self.request.method().clone();
+        *request.uri_mut() = self.request.uri().clone();
+        *request.version_mut() = self.request.version();
+        *request.headers_mut() = self.request.headers().clone();
+        *request.extensions_mut() = std::mem::take(self.request.extensions_mut());
+
+        Context {
+            request,
+            params_data: self.params_data.clone(),
+            raw_params_data: self.raw_params_data.clone(),
+            response: None,
+            router: self.router.clone(),
+        }
+    }
+
+    /// Set the raw values of the params changed by percent-decoding
+    pub(crate) fn set_raw_params(&mut self, raw_params_data: HashMap<String, String>) {
+        self.raw_params_data = Some(raw_params_data);
+    }
+
+    /// Set the router serving the request, for building the urls of named routes
+    pub(crate) fn set_router(&mut self, router: Arc<Router>) {
+        self.router = Some(router);
+    }
+
     /// Access request headers
     pub fn headers(&self) -> &HeaderMap<HeaderValue> {
         self.request.headers()
@@ -110,6 +152,112 @@ impl Context {
             .map_err(|_err| ObsidianError::ParamError(format!("Failed to parse param {}", key)))
     }
 
+    /// Method to get all the params deserialized into a struct.
+    /// The error names the param failed to parse
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use serde::*;
+    ///
+    /// # use obsidian::ContextResult;
+    /// # use obsidian::context::Context;
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// struct UserParams {
+    ///     id: i32,
+    ///     mode: String,
+    /// }
+    ///
+    /// // Assumming ctx contains params for id and mode
+    /// async fn get_handler(ctx: Context) -> ContextResult {
+    ///     let params: UserParams = ctx.params()?;
+    ///
+    ///     assert_eq!(params.id, 1);
+    ///     assert_eq!(params.mode, "edit".to_string());
+    ///
+    ///     ctx.build("").ok()
+    /// }
+    /// ```
+    pub fn params<T: DeserializeOwned>(&self) -> Result<T, ObsidianError> {
+        let cow_params_map = self
+            .params_data
+            .iter()
+            .map(|(key, value)| {
+                (
+                    Cow::from(key.as_str()),
+                    Cow::from(std::slice::from_ref(value)),
+                )
+            })
+            .collect::<HashMap<Cow<str>, Cow<[String]>>>();
+
+        from_params_map(&cow_params_map).map_err(|err| ObsidianError::ParamError(err.to_string()))
+    }
+
+    /// Method to get the param as it appears in the request path, before percent-decoding.
+    /// `param` returns the decoded value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use obsidian::ContextResult;
+    /// # use obsidian::context::Context;
+    ///
+    /// // Assuming the request path is "/files/my%20doc" for the route "/files/:name"
+    /// async fn get_handler(ctx: Context) -> ContextResult {
+    ///     let name: String = ctx.param("name")?;
+    ///
+    ///     assert_eq!(name, "my doc");
+    ///     assert_eq!(ctx.raw_param("name"), Some("my%20doc"));
+    ///
+    ///     ctx.build("").ok()
+    /// }
+    /// ```
+    pub fn raw_param(&self, key: &str) -> Option<&str> {
+        self.raw_params_data
+            .as_ref()
+            .and_then(|raw_params| raw_params.get(key))
+            .or_else(|| self.params_data.get(key))
+            .map(String::as_str)
+    }
+
+    /// Build the url of the named route with the params through the router serving the request
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use obsidian::ContextResult;
+    /// # use obsidian::context::Context;
+    ///
+    /// // Assuming the route named "user" is registered at "/users/:id"
+    /// async fn redirect_handler(ctx: Context) -> ContextResult {
+    ///     let url = ctx.url_for("user", &[("id", "1")])?;
+    ///
+    ///     assert_eq!(url, "/users/1");
+    ///
+    ///     ctx.build(url).ok()
+    /// }
+    /// ```
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, ObsidianError> {
+        self.router()?.url_for(name, params)
+    }
+
+    /// Build the url of the named route with the params, and the query serialized as the query string
+    pub fn url_for_with_query(
+        &self,
+        name: &str,
+        params: &[(&str, &str)],
+        query: &impl Serialize,
+    ) -> Result<String, ObsidianError> {
+        self.router()?.url_for_with_query(name, params, query)
+    }
+
+    pub(crate) fn router(&self) -> Result<&Router, ObsidianError> {
+        self.router.as_deref().ok_or_else(|| {
+            ObsidianError::GeneralError("Router is not available in the context".to_string())
+        })
+    }
+
     /// Method to get the string query data from the request url.
     /// Untagged is not supported
     ///
@@ -137,13 +285,9 @@ impl Context {
     /// }
     /// ```
     pub fn query_params<T: DeserializeOwned>(&mut self) -> Result<T, ObsidianError> {
-        let query = match self.uri().query() {
-            Some(query) => query,
-            _ => "",
-        }
-        .as_bytes();
+        let query = self.uri().query().unwrap_or_default().as_bytes();
 
-        Self::parse_queries(&query)
+        Self::parse_queries(query)
     }
 
     /// Method to get the forms query data from the request body.
@@ -186,9 +330,92 @@ impl Context {
         Self::parse_queries(buf.chunk())
     }
 
-    /// Form value merge with Params
-    pub fn form_with_param<T: DeserializeOwned>(&mut self) -> Result<T, ObsidianError> {
-        unimplemented!()
+    /// Method to get the forms query data from the request body merged with the route params.
+    /// Body is consumed after calling this method.
+    /// The params take precedence over the form, a form key equal to a param must carry the same value,
+    /// otherwise `ObsidianError::ParamError` is returned
+    ///
+    /// # Example
+    /// ```
+    /// # use serde::*;
+    ///
+    /// # use obsidian::context::Context;
+    /// # use obsidian::{ContextResult, StatusCode};
+    ///
+    /// #[derive(Deserialize, Serialize, Debug)]
+    /// struct FormResult {
+    ///     id: i32,
+    ///     mode: String,
+    /// }
+    ///
+    /// // Assume ctx is routed by "/users/:id" with form query data {mode=edit}
+    /// async fn post_handler(mut ctx: Context) -> ContextResult {
+    ///     let result: FormResult = ctx.form_with_param().await?;
+    ///
+    ///     assert_eq!(result.id, 1);
+    ///     assert_eq!(result.mode, "edit".to_string());
+    ///
+    ///     ctx.build("").ok()
+    /// }
+    /// ```
+    pub async fn form_with_param<T: DeserializeOwned>(&mut self) -> Result<T, ObsidianError> {
+        let body = self.take_body();
+
+        let buf = match body::aggregate(body).await {
+            Ok(buf) => buf,
+            _ => {
+                return Err(ObsidianError::NoneError);
+            }
+        };
+
+        let mut form_map = Self::parse_form_map(buf.chunk());
+
+        for (key, param) in self.params_data.iter() {
+            if let Some(values) = form_map.get(key) {
+                if values.iter().any(|value| value != param) {
+                    return Err(Self::conflict_error(key));
+                }
+            }
+
+            form_map.insert(key.clone(), vec![param.clone()]);
+        }
+
+        Self::deserialize_form_map(&form_map)
+    }
+
+    /// Method to get the fields of the `multipart/form-data` body as `Multipart`, which streams
+    /// the fields one after another. Body is consumed after calling this method.
+    /// Fail if the `Content-Type` is not `multipart/form-data` with a boundary
+    ///
+    /// # Example
+    /// ```
+    /// # use obsidian::context::Context;
+    /// # use obsidian::ContextResult;
+    ///
+    /// async fn upload_handler(mut ctx: Context) -> ContextResult {
+    ///     let mut multipart = ctx.multipart()?;
+    ///     multipart.set_total_limit(50 * 1024 * 1024);
+    ///
+    ///     while let Some(mut field) = multipart.next_field().await? {
+    ///         println!("{} {:?} {:?}", field.name(), field.filename(), field.content_type());
+    ///
+    ///         while let Some(chunk) = field.chunk().await? {
+    ///             println!("received {} bytes", chunk.len());
+    ///         }
+    ///     }
+    ///
+    ///     ctx.build("uploaded").ok()
+    /// }
+    /// ```
+    pub fn multipart(&mut self) -> Result<Multipart, ObsidianError> {
+        let boundary = self
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(multipart::boundary_of)
+            .ok_or(MultipartError::InvalidContentType)?;
+
+        Ok(Multipart::new(self.take_body(), &boundary))
     }
 
     /// Method to get the json data from the request body. Body is consumed after calling this method.
@@ -251,9 +478,52 @@ impl Context {
         Ok(serde_json::from_slice(buf.chunk())?)
     }
 
-    /// Json value merged with Params
-    pub fn json_with_param<T: DeserializeOwned>(&mut self) -> Result<T, ObsidianError> {
-        unimplemented!()
+    /// Method to get the json object from the request body merged with the route params.
+    /// Body is consumed after calling this method.
+    /// The params take precedence over the json, a json key equal to a param must carry the same
+    /// string, number or boolean value, otherwise `ObsidianError::ParamError` is returned
+    ///
+    /// # Example
+    /// ```
+    /// # use serde::*;
+    ///
+    /// # use obsidian::context::Context;
+    /// # use obsidian::{ContextResult, StatusCode};
+    ///
+    /// #[derive(Deserialize, Serialize, Debug)]
+    /// struct JsonResult {
+    ///     id: i32,
+    ///     mode: String,
+    /// }
+    ///
+    /// // Assume ctx is routed by "/users/:id" with json data {mode:'edit'}
+    /// async fn post_handler(mut ctx: Context) -> ContextResult {
+    ///     let result: JsonResult = ctx.json_with_param().await?;
+    ///
+    ///     assert_eq!(result.id, 1);
+    ///     assert_eq!(result.mode, "edit".to_string());
+    ///
+    ///     ctx.build("").ok()
+    /// }
+    /// ```
+    pub async fn json_with_param<T: DeserializeOwned>(&mut self) -> Result<T, ObsidianError> {
+        let body: serde_json::Map<String, serde_json::Value> = self.json().await?;
+
+        for (key, param) in self.params_data.iter() {
+            let conflicted = match body.get(key) {
+                None => false,
+                Some(serde_json::Value::String(value)) => value != param,
+                Some(value @ serde_json::Value::Number(_))
+                | Some(value @ serde_json::Value::Bool(_)) => &value.to_string() != param,
+                Some(_) => true,
+            };
+
+            if conflicted {
+                return Err(Self::conflict_error(key));
+            }
+        }
+
+        Ok(from_json_with_params(body, &self.params_data)?)
     }
 
     /// Consumes body of the request and replace it with empty body.
@@ -290,21 +560,29 @@ impl Context {
     }
 
     fn parse_queries<T: DeserializeOwned>(query: &[u8]) -> Result<T, ObsidianError> {
+        Self::deserialize_form_map(&Self::parse_form_map(query))
+    }
+
+    fn parse_form_map(query: &[u8]) -> HashMap<String, Vec<String>> {
         let mut parsed_form_map: HashMap<String, Vec<String>> = HashMap::default();
-        let mut cow_form_map = HashMap::<Cow<str>, Cow<[String]>>::default();
 
         // Parse and merge chunks with same name key
         form_urlencoded::parse(query)
             .into_owned()
             .for_each(|(key, val)| {
                 if !val.is_empty() {
-                    parsed_form_map
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(val);
+                    parsed_form_map.entry(key).or_default().push(val);
                 }
             });
 
+        parsed_form_map
+    }
+
+    fn deserialize_form_map<T: DeserializeOwned>(
+        parsed_form_map: &HashMap<String, Vec<String>>,
+    ) -> Result<T, ObsidianError> {
+        let mut cow_form_map = HashMap::<Cow<str>, Cow<[String]>>::default();
+
         // Wrap vec with cow pointer
         parsed_form_map.iter().for_each(|(key, val)| {
             cow_form_map
@@ -314,6 +592,10 @@ impl Context {
 
         Ok(from_cow_map(&cow_form_map)?)
     }
+
+    fn conflict_error(key: &str) -> ObsidianError {
+        ObsidianError::ParamError(format!("Param {} conflicts with the body", key))
+    }
 }
 
 pub struct ResponseBuilder {
@@ -431,6 +713,42 @@ mod test {
         let _id: i32 = ctx.param("id").unwrap();
     }
 
+    #[test]
+    fn test_params_struct() -> Result<(), ObsidianError> {
+        let mut params_map = HashMap::default();
+
+        params_map.insert("id".to_string(), "1".to_string());
+        params_map.insert("mode".to_string(), "edit".to_string());
+
+        let ctx = Context::new(Request::new(Body::from("")), params_map);
+
+        let actual_result: FormResult = ctx.params()?;
+        let expected_result = FormResult {
+            id: 1,
+            mode: "edit".to_string(),
+        };
+
+        assert_eq!(actual_result, expected_result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_params_struct_with_invalid_value() {
+        let mut params_map = HashMap::default();
+
+        params_map.insert("id".to_string(), "abc".to_string());
+        params_map.insert("mode".to_string(), "edit".to_string());
+
+        let ctx = Context::new(Request::new(Body::from("")), params_map);
+
+        let result: Result<FormResult, _> = ctx.params();
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Failed to parse id: invalid digit found in string"
+        );
+    }
+
     #[test]
     fn test_string_query() -> Result<(), ObsidianError> {
         let params_map = HashMap::default();
@@ -542,4 +860,143 @@ mod test {
             Ok(())
         })
     }
+
+    #[test]
+    fn test_json_with_extra_field() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            let params = HashMap::default();
+            let request = Request::new(Body::from("{\"id\":1,\"mode\":\"edit\"}"));
+
+            let mut ctx = Context::new(request, params);
+
+            let actual_result: JsonExtraResult = ctx.json().await?;
+            let expected_result = JsonExtraResult {
+                id: 1,
+                mode: "edit".to_string(),
+                extra: i32::default(),
+            };
+
+            assert_eq!(actual_result, expected_result);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_form_with_param() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            let mut params = HashMap::default();
+            params.insert("id".to_string(), "1".to_string());
+            let request = Request::new(Body::from("id=1&mode=edit"));
+
+            let mut ctx = Context::new(request, params);
+
+            let actual_result: FormResult = ctx.form_with_param().await?;
+            let expected_result = FormResult {
+                id: 1,
+                mode: "edit".to_string(),
+            };
+
+            assert_eq!(actual_result, expected_result);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_form_with_conflicting_param() {
+        task::block_on(async {
+            let mut params = HashMap::default();
+            params.insert("id".to_string(), "1".to_string());
+            let request = Request::new(Body::from("id=2&mode=edit"));
+
+            let mut ctx = Context::new(request, params);
+
+            let result: Result<FormResult, _> = ctx.form_with_param().await;
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Param id conflicts with the body"
+            );
+        })
+    }
+
+    #[test]
+    fn test_json_with_param() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            let mut params = HashMap::default();
+            params.insert("id".to_string(), "1".to_string());
+            let request = Request::new(Body::from("{\"mode\":\"edit\",\"extra\":2}"));
+
+            let mut ctx = Context::new(request, params);
+
+            let actual_result: JsonExtraResult = ctx.json_with_param().await?;
+            let expected_result = JsonExtraResult {
+                id: 1,
+                mode: "edit".to_string(),
+                extra: 2,
+            };
+
+            assert_eq!(actual_result, expected_result);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_json_with_conflicting_param() {
+        task::block_on(async {
+            let mut params = HashMap::default();
+            params.insert("id".to_string(), "1".to_string());
+
+            let request = Request::new(Body::from("{\"id\":1,\"mode\":\"edit\"}"));
+            let mut ctx = Context::new(request, params.clone());
+            let result: Result<JsonResult, _> = ctx.json_with_param().await;
+
+            assert!(result.is_ok());
+
+            let request = Request::new(Body::from("{\"id\":2,\"mode\":\"edit\"}"));
+            let mut ctx = Context::new(request, params);
+            let result: Result<JsonResult, _> = ctx.json_with_param().await;
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Param id conflicts with the body"
+            );
+        })
+    }
+
+    #[test]
+    fn test_multipart() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            let body = "--boundary\r\nContent-Disposition: form-data; name=\"id\"\r\n\r\n1\r\n--boundary\r\nContent-Disposition: form-data; name=\"mode\"\r\n\r\nedit\r\n--boundary--\r\n";
+            let request = Request::builder()
+                .header("Content-Type", "multipart/form-data; boundary=boundary")
+                .body(Body::from(body))
+                .unwrap();
+
+            let mut ctx = Context::new(request, HashMap::default());
+
+            let actual_result: FormResult = ctx.multipart()?.form().await?;
+            let expected_result = FormResult {
+                id: 1,
+                mode: "edit".to_string(),
+            };
+
+            assert_eq!(actual_result, expected_result);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_multipart_with_invalid_content_type() {
+        let request = Request::builder()
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(Body::from("id=1&mode=edit"))
+            .unwrap();
+
+        let mut ctx = Context::new(request, HashMap::default());
+
+        assert_eq!(
+            ctx.multipart().unwrap_err().to_string(),
+            MultipartError::InvalidContentType.to_string()
+        );
+    }
 }
@@ -0,0 +1,747 @@
+//! Streaming parser of the `multipart/form-data` body, see [`Context::multipart`]
+
+use async_std::fs::OpenOptions;
+use async_std::io::WriteExt;
+use async_std::stream::Stream;
+use hyper::body::{Bytes, HttpBody};
+use serde::de::DeserializeOwned;
+
+use std::collections::HashMap;
+use std::future::poll_fn;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{ready, Context as TaskContext, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Context;
+use crate::error::MultipartError;
+use crate::{Body, ObsidianError};
+
+/// Headers of a field larger than this are rejected
+const MAX_HEADERS_SIZE: usize = 8 * 1024;
+
+/// Default limit of the data size of each field, 1 MiB
+const DEFAULT_FIELD_LIMIT: usize = 1024 * 1024;
+
+/// Default limit of the size of the whole body, 8 MiB
+const DEFAULT_TOTAL_LIMIT: usize = 8 * 1024 * 1024;
+
+/// Counter keeping the names of the saved files unique within the process
+static SAVED_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Fields of the `multipart/form-data` body, read one after another through `next_field`.
+/// The body is read only as far as the fields are consumed
+///
+/// # Example
+/// ```
+/// # use obsidian::context::Context;
+/// # use obsidian::ContextResult;
+///
+/// async fn upload_handler(mut ctx: Context) -> ContextResult {
+///     let mut multipart = ctx.multipart()?;
+///     multipart.set_field_limit(10 * 1024 * 1024);
+///
+///     while let Some(field) = multipart.next_field().await? {
+///         match field.filename() {
+///             Some(_) => {
+///                 let path = field.save_temp().await?;
+///                 println!("saved to {}", path.display());
+///             }
+///             None => {
+///                 let name = field.name().to_string();
+///                 println!("{}: {}", name, field.text().await?);
+///             }
+///         }
+///     }
+///
+///     ctx.build("uploaded").ok()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Multipart {
+    body: Body,
+    /// `--` followed by the boundary, starting the first field
+    boundary: Vec<u8>,
+    /// CRLF followed by the boundary, ending the data of a field
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    state: State,
+    field_limit: usize,
+    total_limit: usize,
+    total_size: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Before the first boundary
+    Preamble,
+    /// After a boundary, either the headers of the next field or the end of the body follow
+    Headers,
+    /// Inside the data of a field
+    Data,
+    /// After the closing boundary
+    End,
+}
+
+impl Multipart {
+    pub(crate) fn new(body: Body, boundary: &str) -> Self {
+        let boundary = format!("--{}", boundary).into_bytes();
+        let mut delimiter = b"\r\n".to_vec();
+        delimiter.extend_from_slice(&boundary);
+
+        Multipart {
+            body,
+            boundary,
+            delimiter,
+            buffer: Vec::new(),
+            state: State::Preamble,
+            field_limit: DEFAULT_FIELD_LIMIT,
+            total_limit: DEFAULT_TOTAL_LIMIT,
+            total_size: 0,
+        }
+    }
+
+    /// Limit the data size of each field in bytes, 1 MiB by default.
+    /// The field exceeding the limit is answered with 413 Payload Too Large
+    pub fn set_field_limit(&mut self, limit: usize) {
+        self.field_limit = limit;
+    }
+
+    /// Limit the size of the whole body in bytes, 8 MiB by default.
+    /// The body exceeding the limit is answered with 413 Payload Too Large
+    pub fn set_total_limit(&mut self, limit: usize) {
+        self.total_limit = limit;
+    }
+
+    /// Get the next field, None after the closing boundary.
+    /// The data of the previous field which is not read is skipped
+    pub async fn next_field(&mut self) -> Result<Option<Field<'_>>, ObsidianError> {
+        loop {
+            match self.state {
+                State::Preamble => match find(&self.buffer, &self.boundary) {
+                    Some(index) => {
+                        self.buffer.drain(..index + self.boundary.len());
+                        self.state = State::Headers;
+                    }
+                    None => {
+                        // Keep the tail which may be the beginning of the boundary
+                        let skipped = self.buffer.len().saturating_sub(self.boundary.len() - 1);
+                        self.buffer.drain(..skipped);
+                        self.fill().await?;
+                    }
+                },
+                State::Headers => {
+                    if self.buffer.len() < 2 {
+                        self.fill().await?;
+                    } else if self.buffer.starts_with(b"--") {
+                        self.state = State::End;
+                    } else if let Some(index) = find(&self.buffer, b"\r\n\r\n") {
+                        let headers = FieldHeaders::parse(&self.buffer[..index])?;
+                        self.buffer.drain(..index + 4);
+                        self.state = State::Data;
+
+                        return Ok(Some(Field::new(self, headers)));
+                    } else if self.buffer.len() > MAX_HEADERS_SIZE {
+                        return Err(MultipartError::InvalidHeaders.into());
+                    } else {
+                        self.fill().await?;
+                    }
+                }
+                State::Data => while poll_fn(|cx| self.poll_data(cx)).await?.is_some() {},
+                State::End => return Ok(None),
+            }
+        }
+    }
+
+    /// Deserialize the remaining text fields into a struct, file fields are skipped
+    ///
+    /// # Example
+    /// ```
+    /// # use serde::*;
+    ///
+    /// # use obsidian::context::Context;
+    /// # use obsidian::ContextResult;
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// struct Profile {
+    ///     name: String,
+    ///     age: i32,
+    /// }
+    ///
+    /// async fn profile_handler(mut ctx: Context) -> ContextResult {
+    ///     let profile: Profile = ctx.multipart()?.form().await?;
+    ///
+    ///     ctx.build(profile.name).ok()
+    /// }
+    /// ```
+    pub async fn form<T: DeserializeOwned>(&mut self) -> Result<T, ObsidianError> {
+        let mut form_map: HashMap<String, Vec<String>> = HashMap::default();
+
+        while let Some(field) = self.next_field().await? {
+            if field.filename().is_some() {
+                continue;
+            }
+
+            let name = field.name().to_string();
+            let value = field.text().await?;
+
+            if !value.is_empty() {
+                form_map.entry(name).or_default().push(value);
+            }
+        }
+
+        Context::deserialize_form_map(&form_map)
+    }
+
+    async fn fill(&mut self) -> Result<(), ObsidianError> {
+        poll_fn(|cx| self.poll_fill(cx)).await
+    }
+
+    /// Read the next chunk of the body into the buffer
+    fn poll_fill(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), ObsidianError>> {
+        match ready!(Pin::new(&mut self.body).poll_data(cx)) {
+            Some(Ok(chunk)) => {
+                self.total_size += chunk.len();
+
+                if self.total_size > self.total_limit {
+                    let limit = self.total_limit;
+
+                    return Poll::Ready(Err(MultipartError::BodyTooLarge { limit }.into()));
+                }
+
+                self.buffer.extend_from_slice(&chunk);
+                Poll::Ready(Ok(()))
+            }
+            Some(Err(err)) => Poll::Ready(Err(err.into())),
+            None => Poll::Ready(Err(MultipartError::IncompleteStream.into())),
+        }
+    }
+
+    /// Take the next chunk of the data of current field, None at the end of the field
+    fn poll_data(
+        &mut self,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<Option<Bytes>, ObsidianError>> {
+        loop {
+            if self.state != State::Data {
+                return Poll::Ready(Ok(None));
+            }
+
+            if let Some(index) = find(&self.buffer, &self.delimiter) {
+                let rest = self.buffer.split_off(index + self.delimiter.len());
+                let mut data = std::mem::replace(&mut self.buffer, rest);
+                data.truncate(index);
+                self.state = State::Headers;
+
+                if !data.is_empty() {
+                    return Poll::Ready(Ok(Some(Bytes::from(data))));
+                }
+            } else {
+                // Keep the tail which may be the beginning of the delimiter
+                let available = self.buffer.len().saturating_sub(self.delimiter.len() - 1);
+
+                if available > 0 {
+                    let rest = self.buffer.split_off(available);
+                    let data = std::mem::replace(&mut self.buffer, rest);
+
+                    return Poll::Ready(Ok(Some(Bytes::from(data))));
+                }
+
+                ready!(self.poll_fill(cx))?;
+            }
+        }
+    }
+}
+
+/// Field of the multipart body. The data is streamed in chunks through `chunk` or the `Stream` impl,
+/// or consumed at once through `bytes`, `text`, `save_to` and `save_temp`
+#[derive(Debug)]
+pub struct Field<'a> {
+    multipart: &'a mut Multipart,
+    headers: FieldHeaders,
+    size: usize,
+}
+
+impl<'a> Field<'a> {
+    fn new(multipart: &'a mut Multipart, headers: FieldHeaders) -> Self {
+        Field {
+            multipart,
+            headers,
+            size: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.headers.name
+    }
+
+    /// File name of the file field, None for the text field
+    pub fn filename(&self) -> Option<&str> {
+        self.headers.filename.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.content_type.as_deref()
+    }
+
+    /// Get the next chunk of the data, None at the end of the field
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, ObsidianError> {
+        poll_fn(|cx| self.poll_chunk(cx)).await
+    }
+
+    /// Buffer the whole data of the field
+    pub async fn bytes(mut self) -> Result<Vec<u8>, ObsidianError> {
+        let mut data = Vec::new();
+
+        while let Some(chunk) = self.chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(data)
+    }
+
+    /// Buffer the whole data of the field as utf-8 text
+    pub async fn text(self) -> Result<String, ObsidianError> {
+        let name = self.headers.name.clone();
+
+        String::from_utf8(self.bytes().await?)
+            .map_err(|_| MultipartError::InvalidUtf8 { name }.into())
+    }
+
+    /// Write the data into a new file with unique name in the directory, returning the path of the file.
+    /// The file name sent by the client is not used, the partial file is removed on error
+    pub async fn save_to(mut self, dir: impl AsRef<Path>) -> Result<PathBuf, ObsidianError> {
+        let path = dir.as_ref().join(unique_file_name());
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?;
+
+        let result = async {
+            while let Some(chunk) = self.chunk().await? {
+                file.write_all(&chunk).await?;
+            }
+
+            file.flush().await?;
+            Ok::<_, ObsidianError>(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(path),
+            Err(err) => {
+                drop(file);
+                let _ = async_std::fs::remove_file(&path).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Write the data into a new file in the temp directory of the system, see `save_to`
+    pub async fn save_temp(self) -> Result<PathBuf, ObsidianError> {
+        self.save_to(std::env::temp_dir()).await
+    }
+
+    fn poll_chunk(
+        &mut self,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<Option<Bytes>, ObsidianError>> {
+        let chunk = ready!(self.multipart.poll_data(cx))?;
+
+        if let Some(chunk) = &chunk {
+            self.size += chunk.len();
+
+            if self.size > self.multipart.field_limit {
+                return Poll::Ready(Err(MultipartError::FieldTooLarge {
+                    name: self.headers.name.clone(),
+                    limit: self.multipart.field_limit,
+                }
+                .into()));
+            }
+        }
+
+        Poll::Ready(Ok(chunk))
+    }
+}
+
+impl Stream for Field<'_> {
+    type Item = Result<Bytes, ObsidianError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_chunk(cx).map(Result::transpose)
+    }
+}
+
+#[derive(Debug)]
+struct FieldHeaders {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+}
+
+impl FieldHeaders {
+    /// Parse the headers following the boundary, starting with the rest of the boundary line
+    fn parse(headers: &[u8]) -> Result<Self, MultipartError> {
+        let headers = std::str::from_utf8(headers).map_err(|_| MultipartError::InvalidHeaders)?;
+        let mut lines = headers.split("\r\n");
+
+        // Only transport padding is allowed after the boundary
+        if !lines.next().unwrap_or_default().trim().is_empty() {
+            return Err(MultipartError::InvalidHeaders);
+        }
+
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = None;
+
+        for line in lines {
+            let (key, value) = line.split_once(':').ok_or(MultipartError::InvalidHeaders)?;
+            let value = value.trim();
+
+            if key.trim().eq_ignore_ascii_case("content-disposition") {
+                for (param, param_value) in header_params(value) {
+                    if param.eq_ignore_ascii_case("name") {
+                        name = Some(param_value);
+                    } else if param.eq_ignore_ascii_case("filename") {
+                        filename = Some(param_value);
+                    }
+                }
+            } else if key.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.to_string());
+            }
+        }
+
+        Ok(FieldHeaders {
+            name: name.ok_or(MultipartError::MissingName)?,
+            filename,
+            content_type,
+        })
+    }
+}
+
+/// Boundary of the `multipart/form-data` content type, None for other content types
+pub(crate) fn boundary_of(content_type: &str) -> Option<String> {
+    let (media_type, params) = content_type.split_once(';')?;
+
+    if !media_type
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+
+    header_params(params)
+        .into_iter()
+        .find(|(param, _)| param.eq_ignore_ascii_case("boundary"))
+        .map(|(_, boundary)| boundary)
+        .filter(|boundary| !boundary.is_empty() && boundary.len() <= 70)
+}
+
+/// Parse the `key=value` params separated by `;`, the value can be quoted with escapes.
+/// The items without `=`, such as the leading `form-data`, are skipped
+fn header_params(value: &str) -> Vec<(String, String)> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    // Split by the `;` outside the quotes
+    for c in value.chars() {
+        match c {
+            ';' if !quoted => segments.push(std::mem::take(&mut segment)),
+            c => {
+                if quoted && !escaped && c == '\\' {
+                    escaped = true;
+                } else {
+                    if !escaped && c == '"' {
+                        quoted = !quoted;
+                    }
+                    escaped = false;
+                }
+                segment.push(c);
+            }
+        }
+    }
+    segments.push(segment);
+
+    segments
+        .iter()
+        .filter_map(|segment| segment.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), unquote(value.trim())))
+        .collect()
+}
+
+fn unquote(value: &str) -> String {
+    let quoted = match value.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => return value.to_string(),
+    };
+    let mut unquoted = String::new();
+    let mut chars = quoted.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+
+    unquoted
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn unique_file_name() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    format!(
+        "obsidian-upload-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        SAVED_FILE_COUNT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::stream::StreamExt;
+    use async_std::task;
+    use serde::Deserialize;
+
+    const BOUNDARY: &str = "X-BOUNDARY";
+
+    fn body_of(parts: &[(&str, Option<&str>, &str)]) -> String {
+        let mut body = "preamble\r\n".to_string();
+
+        for (name, filename, data) in parts.iter() {
+            body.push_str(&format!("--{}\r\n", BOUNDARY));
+
+            match filename {
+                Some(filename) => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: text/plain\r\n\r\n",
+                    name, filename
+                )),
+                None => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                    name
+                )),
+            }
+
+            body.push_str(data);
+            body.push_str("\r\n");
+        }
+
+        body.push_str(&format!("--{}--\r\n", BOUNDARY));
+        body
+    }
+
+    /// Multipart reading the body in chunks of the size
+    fn multipart_of(body: String, chunk_size: usize) -> Multipart {
+        let chunks = body
+            .into_bytes()
+            .chunks(chunk_size)
+            .map(|chunk| Ok::<_, std::io::Error>(chunk.to_vec()))
+            .collect::<Vec<_>>();
+
+        Multipart::new(
+            Body::wrap_stream(async_std::stream::from_iter(chunks)),
+            BOUNDARY,
+        )
+    }
+
+    #[test]
+    fn test_next_field() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            for chunk_size in [1, 3, 7, 1024].iter() {
+                let body = body_of(&[
+                    ("title", None, "hello"),
+                    ("file", Some("a.txt"), "line1\r\n--X-BOUND\r\nline2"),
+                    ("empty", None, ""),
+                ]);
+                let mut multipart = multipart_of(body, *chunk_size);
+
+                let field = multipart.next_field().await?.unwrap();
+                assert_eq!(field.name(), "title");
+                assert_eq!(field.filename(), None);
+                assert_eq!(field.content_type(), None);
+                assert_eq!(field.text().await?, "hello");
+
+                let field = multipart.next_field().await?.unwrap();
+                assert_eq!(field.name(), "file");
+                assert_eq!(field.filename(), Some("a.txt"));
+                assert_eq!(field.content_type(), Some("text/plain"));
+                assert_eq!(field.bytes().await?, b"line1\r\n--X-BOUND\r\nline2");
+
+                let field = multipart.next_field().await?.unwrap();
+                assert_eq!(field.name(), "empty");
+                assert_eq!(field.text().await?, "");
+
+                assert!(multipart.next_field().await?.is_none());
+                assert!(multipart.next_field().await?.is_none());
+            }
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_skip_unread_field() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            let body = body_of(&[("file", Some("a.txt"), "content"), ("title", None, "hello")]);
+            let mut multipart = multipart_of(body, 4);
+
+            let mut field = multipart.next_field().await?.unwrap();
+            assert_eq!(field.next().await.unwrap()?, "cont");
+
+            let field = multipart.next_field().await?.unwrap();
+            assert_eq!(field.name(), "title");
+            assert_eq!(field.text().await?, "hello");
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_form() -> Result<(), ObsidianError> {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Profile {
+            name: String,
+            tags: Vec<String>,
+            age: i32,
+        }
+
+        task::block_on(async {
+            let body = body_of(&[
+                ("name", None, "obsidian"),
+                ("avatar", Some("avatar.png"), "binary"),
+                ("tags", None, "rust"),
+                ("tags", None, "web"),
+                ("age", None, "3"),
+            ]);
+            let mut multipart = multipart_of(body, 5);
+
+            let actual_result: Profile = multipart.form().await?;
+            let expected_result = Profile {
+                name: "obsidian".to_string(),
+                tags: vec!["rust".to_string(), "web".to_string()],
+                age: 3,
+            };
+
+            assert_eq!(actual_result, expected_result);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_save_temp() -> Result<(), ObsidianError> {
+        task::block_on(async {
+            let body = body_of(&[("file", Some("../a.txt"), "file content")]);
+            let mut multipart = multipart_of(body, 3);
+
+            let field = multipart.next_field().await?.unwrap();
+            let path = field.save_temp().await?;
+
+            assert_eq!(path.parent(), Some(std::env::temp_dir().as_path()));
+            assert_eq!(std::fs::read_to_string(&path)?, "file content");
+
+            std::fs::remove_file(&path)?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_limits() {
+        task::block_on(async {
+            let body = body_of(&[
+                ("title", None, "hello"),
+                ("file", Some("a.txt"), "too large"),
+            ]);
+            let mut multipart = multipart_of(body.clone(), 3);
+            multipart.set_field_limit(5);
+
+            let field = multipart.next_field().await.unwrap().unwrap();
+            assert_eq!(field.text().await.unwrap(), "hello");
+
+            let field = multipart.next_field().await.unwrap().unwrap();
+            assert_eq!(
+                field.bytes().await.unwrap_err().to_string(),
+                MultipartError::FieldTooLarge {
+                    name: "file".to_string(),
+                    limit: 5
+                }
+                .to_string()
+            );
+
+            let mut multipart = multipart_of(body, 3);
+            multipart.set_total_limit(64);
+
+            let result: Result<HashMap<String, String>, _> = multipart.form().await;
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                MultipartError::BodyTooLarge { limit: 64 }.to_string()
+            );
+        })
+    }
+
+    #[test]
+    fn test_incomplete_stream() {
+        task::block_on(async {
+            let body = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nhello",
+                BOUNDARY
+            );
+            let mut multipart = multipart_of(body, 3);
+
+            let field = multipart.next_field().await.unwrap().unwrap();
+            assert_eq!(
+                field.text().await.unwrap_err().to_string(),
+                MultipartError::IncompleteStream.to_string()
+            );
+
+            let body = format!("--{}\r\nContent-Type: text/plain\r\n\r\nhello", BOUNDARY);
+            let mut multipart = multipart_of(body, 3);
+
+            assert_eq!(
+                multipart.next_field().await.unwrap_err().to_string(),
+                MultipartError::MissingName.to_string()
+            );
+        })
+    }
+
+    #[test]
+    fn test_boundary_of() {
+        assert_eq!(
+            boundary_of("multipart/form-data; boundary=X-BOUNDARY"),
+            Some("X-BOUNDARY".to_string())
+        );
+        assert_eq!(
+            boundary_of("Multipart/Form-Data; charset=utf-8; BOUNDARY=\"a;b\""),
+            Some("a;b".to_string())
+        );
+        assert_eq!(boundary_of("multipart/form-data"), None);
+        assert_eq!(boundary_of("multipart/form-data; boundary="), None);
+        assert_eq!(boundary_of("application/json; boundary=X"), None);
+    }
+
+    #[test]
+    fn test_header_params() {
+        assert_eq!(
+            header_params(r#"form-data; name="files"; filename="a \"b\"; c.txt""#),
+            vec![
+                ("name".to_string(), "files".to_string()),
+                ("filename".to_string(), "a \"b\"; c.txt".to_string()),
+            ]
+        );
+    }
+}
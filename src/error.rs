@@ -1,3 +1,7 @@
+mod multipart_error;
 mod obsidian_error;
+mod route_error;
 
+pub use multipart_error::MultipartError;
 pub use obsidian_error::ObsidianError;
+pub use route_error::RouteError;
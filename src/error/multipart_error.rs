@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+/// Errors occurs while parsing the multipart/form-data body
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MultipartError {
+    /// The `Content-Type` is not `multipart/form-data` with a valid boundary
+    InvalidContentType,
+    /// The headers of the field are not valid or too large
+    InvalidHeaders,
+    /// The `Content-Disposition` of the field is missing or without name
+    MissingName,
+    /// The body ends before the closing boundary
+    IncompleteStream,
+    /// The data of the field exceeds the field size limit
+    FieldTooLarge { name: String, limit: usize },
+    /// The body exceeds the total size limit
+    BodyTooLarge { limit: usize },
+    /// The text field is not valid utf-8
+    InvalidUtf8 { name: String },
+}
+
+impl Display for MultipartError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MultipartError::InvalidContentType => {
+                formatter.write_str("Content-Type is not multipart/form-data with a valid boundary")
+            }
+            MultipartError::InvalidHeaders => {
+                formatter.write_str("Invalid multipart field headers")
+            }
+            MultipartError::MissingName => {
+                formatter.write_str("Multipart field without Content-Disposition name")
+            }
+            MultipartError::IncompleteStream => {
+                formatter.write_str("Multipart body ends before the closing boundary")
+            }
+            MultipartError::FieldTooLarge { ref name, limit } => write!(
+                formatter,
+                "Multipart field '{}' exceeds the limit of {} bytes",
+                name, limit
+            ),
+            MultipartError::BodyTooLarge { limit } => write!(
+                formatter,
+                "Multipart body exceeds the limit of {} bytes",
+                limit
+            ),
+            MultipartError::InvalidUtf8 { ref name } => {
+                write!(formatter, "Multipart field '{}' is not valid utf-8", name)
+            }
+        }
+    }
+}
+
+impl Error for MultipartError {
+    fn description(&self) -> &str {
+        "Multipart Error"
+    }
+}
@@ -1,9 +1,12 @@
 use std::error::Error;
 use std::fmt;
 use std::fmt::Display;
+use std::io;
+use std::time::Duration;
 
 use serde_json::error::Error as JsonError;
 
+use super::MultipartError;
 use crate::router::FormError;
 
 /// Errors occurs in Obsidian framework
@@ -12,7 +15,11 @@ pub enum ObsidianError {
     ParamError(String),
     JsonError(JsonError),
     FormError(FormError),
+    MultipartError(MultipartError),
     GeneralError(String),
+    ServerError(hyper::Error),
+    IoError(io::Error),
+    ShutdownTimeout(Duration),
     NoneError,
 }
 
@@ -22,7 +29,14 @@ impl Display for ObsidianError {
             ObsidianError::ParamError(ref msg) => msg.to_string(),
             ObsidianError::JsonError(ref err) => err.to_string(),
             ObsidianError::FormError(ref err) => err.to_string(),
+            ObsidianError::MultipartError(ref err) => err.to_string(),
             ObsidianError::GeneralError(ref msg) => msg.to_string(),
+            ObsidianError::ServerError(ref err) => err.to_string(),
+            ObsidianError::IoError(ref err) => err.to_string(),
+            ObsidianError::ShutdownTimeout(ref timeout) => format!(
+                "Server did not finish in-flight requests within {:?}",
+                timeout
+            ),
             ObsidianError::NoneError => "Input should not be None".to_string(),
         };
 
@@ -36,12 +50,30 @@ impl From<FormError> for ObsidianError {
     }
 }
 
+impl From<MultipartError> for ObsidianError {
+    fn from(error: MultipartError) -> Self {
+        ObsidianError::MultipartError(error)
+    }
+}
+
 impl From<JsonError> for ObsidianError {
     fn from(error: JsonError) -> Self {
         ObsidianError::JsonError(error)
     }
 }
 
+impl From<hyper::Error> for ObsidianError {
+    fn from(error: hyper::Error) -> Self {
+        ObsidianError::ServerError(error)
+    }
+}
+
+impl From<io::Error> for ObsidianError {
+    fn from(error: io::Error) -> Self {
+        ObsidianError::IoError(error)
+    }
+}
+
 impl Error for ObsidianError {
     fn description(&self) -> &str {
         "Obsidian Error"
@@ -0,0 +1,90 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+use hyper::Method;
+
+/// Errors occurs while registering routes into the router
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RouteError {
+    /// The method is already registered at the path by a route without guards
+    DuplicateMethod { method: Method, path: String },
+    /// The param segment conflicts with another param defined at the same position
+    AmbiguousParam {
+        path: String,
+        segment: String,
+        existing: String,
+    },
+    /// The `*` or `*name` wildcard is not a whole segment or not the last segment of the path
+    InvalidWildcard { path: String },
+    /// The param name is empty, params are not separated by static text, or `?` is misplaced
+    InvalidParam { path: String, segment: String },
+    /// The constraint is not closed or is not a valid regex
+    InvalidConstraint { path: String, segment: String },
+    /// The route name is used by another route
+    DuplicateName { name: String, path: String },
+    /// The sub router is merged into a path which already consists routing data
+    SubRouterConflict { path: String },
+    /// The host pattern consists of empty labels, invalid params or optional params
+    InvalidHost { host: String },
+}
+
+impl Display for RouteError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteError::DuplicateMethod {
+                ref method,
+                ref path,
+            } => write!(
+                formatter,
+                "Duplicated route method '{}' at '{}' detected",
+                method, path
+            ),
+            RouteError::AmbiguousParam {
+                ref path,
+                ref segment,
+                ref existing,
+            } => write!(
+                formatter,
+                "Ambiguous definition between '{}' and '{}' at '{}'",
+                segment, existing, path
+            ),
+            RouteError::InvalidWildcard { ref path } => write!(
+                formatter,
+                "Invalid wildcard at '{}', * or *name must be a whole segment in the last of the path",
+                path
+            ),
+            RouteError::InvalidParam {
+                ref path,
+                ref segment,
+            } => write!(formatter, "Invalid param '{}' at '{}'", segment, path),
+            RouteError::InvalidConstraint {
+                ref path,
+                ref segment,
+            } => write!(
+                formatter,
+                "Invalid param constraint '{}' at '{}'",
+                segment, path
+            ),
+            RouteError::DuplicateName { ref name, ref path } => write!(
+                formatter,
+                "Duplicated route name '{}' at '{}' detected",
+                name, path
+            ),
+            RouteError::SubRouterConflict { ref path } => write!(
+                formatter,
+                "There is conflict between main router and sub router at '{}'. Make sure main router does not consist any routing data in '{}'.",
+                path, path
+            ),
+            RouteError::InvalidHost { ref host } => {
+                write!(formatter, "Invalid host pattern '{}'", host)
+            }
+        }
+    }
+}
+
+impl Error for RouteError {
+    fn description(&self) -> &str {
+        "Route Error"
+    }
+}
@@ -8,6 +8,6 @@ pub mod middleware;
 pub mod router;
 
 pub use app::{App, EndpointExecutor};
-pub use error::ObsidianError;
+pub use error::{MultipartError, ObsidianError, RouteError};
 pub use hyper::{header, Body, HeaderMap, Method, Request, Response, StatusCode, Uri, Version};
 pub use router::ContextResult;
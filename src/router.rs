@@ -1,36 +1,58 @@
+pub mod extract;
+pub mod guard;
 mod handler;
+mod host_pattern;
+mod param_constraint;
 mod req_deserializer;
 mod resource;
 mod responder;
 mod response;
 mod response_body;
 mod route;
+mod route_builder;
+mod route_info;
 mod route_trie;
+mod segment_pattern;
+mod trailing_slash;
 
 use self::route_trie::RouteTrie;
 use crate::context::Context;
+use crate::error::{ObsidianError, RouteError};
 use crate::middleware::Middleware;
 use crate::Method;
 pub use hyper::header;
+use serde::ser::Serialize;
 
-pub use self::handler::{ContextResult, Handler};
+pub use self::guard::Guard;
+pub use self::handler::{ContextResult, ExtractorHandler, Handler, IntoHandler, ResponderHandler};
 pub use self::req_deserializer::{from_cow_map, Error as FormError};
+pub(crate) use self::req_deserializer::{from_json_with_params, from_params_map};
 pub use self::resource::Resource;
 pub use self::responder::Responder;
 pub use self::response::Response;
 pub use self::response_body::ResponseBody;
 pub use self::route::Route;
+pub use self::route_builder::RouteBuilder;
+pub use self::route_info::RouteInfo;
+pub use self::trailing_slash::TrailingSlash;
 
-pub(crate) use self::route_trie::RouteValueResult;
+pub(crate) use self::host_pattern::HostPattern;
+pub(crate) use self::route_trie::{RouteParams, RouteValueResult};
 
+/// Name of the wildcard param capturing the file path of the static file routes
+const STATIC_FILE_PARAM: &str = "static_file_path";
+
+#[derive(Debug)]
 pub struct Router {
     routes: RouteTrie,
+    trailing_slash: TrailingSlash,
 }
 
 impl Clone for Router {
     fn clone(&self) -> Self {
         Router {
             routes: self.routes.clone(),
+            trailing_slash: self.trailing_slash,
         }
     }
 }
@@ -45,32 +67,95 @@ impl Router {
     pub fn new() -> Self {
         Router {
             routes: RouteTrie::new(),
+            trailing_slash: TrailingSlash::default(),
         }
     }
 
-    pub fn get(&mut self, path: &str, handler: impl Handler) {
-        self.insert_route(Method::GET, path, handler);
+    /// Set the policy for the requests whose trailing slash differs from the route path.
+    /// Only the router serving the requests is configured, through `App::set_trailing_slash`,
+    /// as the routes merged through `use_router` follow the policy of the router they are merged into
+    pub(crate) fn set_trailing_slash(&mut self, trailing_slash: TrailingSlash) {
+        self.trailing_slash = trailing_slash;
+    }
+
+    pub fn trailing_slash(&self) -> TrailingSlash {
+        self.trailing_slash
+    }
+
+    /// Match the static segments case-insensitively, set through `App::set_case_insensitive`
+    /// for the same reason as the trailing slash policy
+    pub(crate) fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.routes.set_case_insensitive(case_insensitive);
+    }
+
+    pub fn get<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_get(path, handler))
+    }
+
+    pub fn post<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_post(path, handler))
+    }
+
+    pub fn put<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_put(path, handler))
+    }
+
+    pub fn patch<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_patch(path, handler))
+    }
+
+    pub fn delete<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_delete(path, handler))
     }
 
-    pub fn post(&mut self, path: &str, handler: impl Handler) {
-        self.insert_route(Method::POST, path, handler);
+    /// Register a HEAD handler. Without it, HEAD requests are served by the GET handler
+    pub fn head<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_head(path, handler))
     }
 
-    pub fn put(&mut self, path: &str, handler: impl Handler) {
-        self.insert_route(Method::PUT, path, handler);
+    /// Register an OPTIONS handler. Without it, OPTIONS requests are answered with the
+    /// registered methods in the `Allow` header
+    pub fn options<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_options(path, handler))
     }
 
-    pub fn patch(&mut self, path: &str, handler: impl Handler) {
-        self.insert_route(Method::PATCH, path, handler);
+    /// Register a handler for any request method, including custom methods such as `PROPFIND`
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, router::Router, Method};
+    ///
+    /// let mut router = Router::new();
+    ///
+    /// router.route(Method::from_bytes(b"PROPFIND").unwrap(), "/files", |ctx: Context| async {
+    ///     ctx.build("propfind").ok()
+    /// });
+    /// ```
+    pub fn route<Args>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_route(method, path, handler))
     }
 
-    pub fn delete(&mut self, path: &str, handler: impl Handler) {
-        self.insert_route(Method::DELETE, path, handler);
+    /// Register a fallback handler which is used when no method specific handler matches
+    pub fn any<Args>(&mut self, path: &str, handler: impl IntoHandler<Args>) -> RouteBuilder<'_> {
+        Self::expect_route(self.try_any(path, handler))
     }
 
     /// Apply middleware in the provided route
     pub fn use_service_to(&mut self, path: &str, middleware: impl Middleware) {
-        self.routes.insert_middleware(path, middleware);
+        Self::expect_route(self.try_use_service_to(path, middleware));
     }
 
     /// Apply middleware in current relative route
@@ -78,60 +163,348 @@ impl Router {
         self.routes.insert_default_middleware(middleware);
     }
 
+    /// Set the handler for the requests which match no route under this router, replacing the existing one.
+    /// The fallback of the deepest sub router matching the prefix of the path is run,
+    /// together with the middlewares applied on that prefix.
+    /// Without any fallback the request is answered with plain "404 Not Found"
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, router::Router, StatusCode};
+    ///
+    /// let mut router = Router::new();
+    /// let mut api_router = Router::new();
+    ///
+    /// router.fallback(|ctx: Context| async { ctx.build("index.html").ok() });
+    ///
+    /// api_router.fallback(|ctx: Context| async {
+    ///     ctx.build_json("Not found")
+    ///         .with_status(StatusCode::NOT_FOUND)
+    ///         .ok()
+    /// });
+    ///
+    /// router.use_router("/api", api_router);
+    /// ```
+    pub fn fallback<Args>(&mut self, handler: impl IntoHandler<Args>) {
+        self.routes.set_fallback(handler.into_handler());
+    }
+
     /// Serve static files by the virtual path as the route and directory path as the server file path
     pub fn use_static_to(&mut self, virtual_path: &str, dir_path: &str) {
-        let mut path = String::from(virtual_path);
-        path.push_str("/*");
-
-        self.get(
-            &path,
-            Self::static_virtual_file_handler(virtual_path, dir_path),
-        );
+        Self::expect_route(self.try_use_static_to(virtual_path, dir_path));
     }
 
     /// Serve static files by the directory path as the route and server file path
     pub fn use_static(&mut self, dir_path: &str) {
-        let mut path = String::from(dir_path);
-        path.push_str("/*");
-
-        self.get(&path, Self::static_dir_file_handler);
+        Self::expect_route(self.try_use_static(dir_path));
     }
 
     /// Apply route handler in current relative route
     pub fn use_router(&mut self, path: &str, other: Router) {
-        RouteTrie::insert_sub_route(&mut self.routes, path, other.routes);
+        Self::expect_route(self.try_use_router(path, other));
+    }
+
+    pub fn try_get<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::GET, path, handler)
+    }
+
+    pub fn try_post<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::POST, path, handler)
+    }
+
+    pub fn try_put<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::PUT, path, handler)
+    }
+
+    pub fn try_patch<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::PATCH, path, handler)
+    }
+
+    pub fn try_delete<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::DELETE, path, handler)
+    }
+
+    pub fn try_head<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::HEAD, path, handler)
     }
 
-    pub fn search_route(&self, path: &str) -> Option<RouteValueResult> {
+    pub fn try_options<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(Method::OPTIONS, path, handler)
+    }
+
+    /// Register a handler for the method, returning the conflict instead of panicking
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, router::Router, Method, RouteError};
+    ///
+    /// let mut router = Router::new();
+    ///
+    /// router.try_route(Method::GET, "/users", |ctx: Context| async {
+    ///     ctx.build("users").ok()
+    /// }).unwrap();
+    ///
+    /// let result = router.try_route(Method::GET, "/users", |ctx: Context| async {
+    ///     ctx.build("users").ok()
+    /// });
+    ///
+    /// assert_eq!(
+    ///     result.err(),
+    ///     Some(RouteError::DuplicateMethod {
+    ///         method: Method::GET,
+    ///         path: "/users".to_string()
+    ///     })
+    /// );
+    /// ```
+    pub fn try_route<Args>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        self.insert_route(method, path, handler)
+    }
+
+    pub fn try_any<Args>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        let route = Route::any(handler.into_handler());
+        let method = route.method.clone();
+
+        self.routes.insert_any_route(path, route)?;
+
+        Ok(RouteBuilder::new(&mut self.routes, path, method))
+    }
+
+    pub fn try_use_service_to(
+        &mut self,
+        path: &str,
+        middleware: impl Middleware,
+    ) -> Result<(), RouteError> {
+        self.routes.insert_middleware(path, middleware)
+    }
+
+    pub fn try_use_static_to(
+        &mut self,
+        virtual_path: &str,
+        dir_path: &str,
+    ) -> Result<(), RouteError> {
+        let path = format!("{}/*{}", virtual_path, STATIC_FILE_PARAM);
+
+        self.try_get(&path, Self::static_virtual_file_handler(dir_path))
+            .map(|_| ())
+    }
+
+    pub fn try_use_static(&mut self, dir_path: &str) -> Result<(), RouteError> {
+        self.try_use_static_to(dir_path, dir_path)
+    }
+
+    pub fn try_use_router(&mut self, path: &str, other: Router) -> Result<(), RouteError> {
+        RouteTrie::insert_sub_route(&mut self.routes, path, other.routes)
+    }
+
+    pub fn search_route<'r, 'p>(&'r self, path: &'p str) -> Option<RouteValueResult<'r, 'p>> {
         self.routes.search_route(path)
     }
 
-    fn insert_route(&mut self, method: Method, path: &str, handler: impl Handler) {
-        let route = Route::new(method, handler);
+    /// Search the deepest fallback along the prefixes of the path
+    pub fn search_fallback<'r, 'p>(&'r self, path: &'p str) -> Option<RouteValueResult<'r, 'p>> {
+        self.routes.search_fallback(path)
+    }
+
+    /// List the registered routes with their full path patterns,
+    /// in the order they are tried when searching
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, router::Router, Method};
+    ///
+    /// let mut router = Router::new();
+    /// let mut user_router = Router::new();
+    ///
+    /// user_router
+    ///     .get("/:id", |ctx: Context| async { ctx.build("user").ok() })
+    ///     .name("user");
+    ///
+    /// router.use_router("/users", user_router);
+    ///
+    /// let route = router.routes().next().unwrap();
+    ///
+    /// assert_eq!(route.method, Method::GET);
+    /// assert_eq!(route.path, "/users/:id");
+    /// assert_eq!(route.name.as_deref(), Some("user"));
+    /// ```
+    pub fn routes(&self) -> impl Iterator<Item = RouteInfo> {
+        self.routes.routes().into_iter()
+    }
+
+    /// Built-in handler serving the route table of the app as json
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{router::Router, App};
+    ///
+    /// let mut app: App = App::new();
+    /// app.get("/admin/routes", Router::route_table);
+    /// ```
+    pub async fn route_table(ctx: Context) -> ContextResult {
+        let routes = ctx.router()?.routes().collect::<Vec<RouteInfo>>();
+
+        ctx.build_json(routes).ok()
+    }
 
-        self.routes.insert_route(path, route);
+    /// Build the url of the named route with the params, including the prefixes of the sub routers.
+    /// The params are percent-encoded while the static segments are kept as registered,
+    /// and the optional param is omitted if it is not provided.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, router::Router};
+    ///
+    /// let mut router = Router::new();
+    /// let mut admin_router = Router::new();
+    ///
+    /// admin_router
+    ///     .get("/users/:id/:tab?", |ctx: Context| async { ctx.build("user").ok() })
+    ///     .name("admin_user");
+    ///
+    /// router.use_router("/admin", admin_router);
+    ///
+    /// assert_eq!(
+    ///     router.url_for("admin_user", &[("id", "Jun Kai")]).unwrap(),
+    ///     "/admin/users/Jun%20Kai"
+    /// );
+    /// ```
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, ObsidianError> {
+        self.routes.url_for(name, params)
     }
 
-    fn static_virtual_file_handler(virtual_path: &str, dir_path: &str) -> impl Handler {
+    /// Build the url of the named route with the params, and the query serialized as the query string
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, router::Router};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Pagination {
+    ///     page: i32,
+    ///     sort: String,
+    /// }
+    ///
+    /// let mut router = Router::new();
+    ///
+    /// router
+    ///     .get("/users", |ctx: Context| async { ctx.build("users").ok() })
+    ///     .name("users");
+    ///
+    /// let query = Pagination {
+    ///     page: 2,
+    ///     sort: "name asc".to_string(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     router.url_for_with_query("users", &[], &query).unwrap(),
+    ///     "/users?page=2&sort=name+asc"
+    /// );
+    /// ```
+    pub fn url_for_with_query(
+        &self,
+        name: &str,
+        params: &[(&str, &str)],
+        query: &impl Serialize,
+    ) -> Result<String, ObsidianError> {
+        let mut url = self.url_for(name, params)?;
+        let query = serde_urlencoded::to_string(query)
+            .map_err(|err| ObsidianError::GeneralError(err.to_string()))?;
+
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query);
+        }
+
+        Ok(url)
+    }
+
+    fn insert_route<Args>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<Args>,
+    ) -> Result<RouteBuilder<'_>, RouteError> {
+        let route = Route::new(method.clone(), handler.into_handler());
+
+        self.routes.insert_route(path, route)?;
+
+        Ok(RouteBuilder::new(&mut self.routes, path, method))
+    }
+
+    /// Apply the root middlewares of the parent router before the root middlewares of this router
+    pub(crate) fn inherit_middlewares(&mut self, parent: &Router) {
+        self.routes
+            .prepend_default_middlewares(parent.routes.default_middlewares());
+    }
+
+    /// Follow the trailing slash policy and the case sensitivity of the parent router
+    pub(crate) fn inherit_settings(&mut self, parent: &Router) {
+        self.trailing_slash = parent.trailing_slash;
+        self.routes
+            .set_case_insensitive(parent.routes.case_insensitive());
+    }
+
+    /// Panic on the route error for the registrations which do not return the error
+    fn expect_route<T>(result: Result<T, RouteError>) -> T {
+        match result {
+            Ok(value) => value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn static_virtual_file_handler(dir_path: &str) -> impl Handler {
         let dir_path = dir_path
             .split('/')
             .filter(|key| !key.is_empty())
             .map(|x| x.to_string())
             .collect::<Vec<String>>();
 
-        let virtual_path_len = virtual_path
-            .split('/')
-            .filter(|key| !key.is_empty())
-            .count();
-
         move |ctx: Context| {
             let mut dir_path = dir_path.clone();
+            // Decoded path may contain "..", which must not escape the directory
             let mut relative_path = ctx
-                .uri()
-                .path()
+                .param::<String>(STATIC_FILE_PARAM)
+                .unwrap_or_default()
                 .split('/')
-                .filter(|key| !key.is_empty())
-                .skip(virtual_path_len)
+                .filter(|key| !key.is_empty() && *key != "." && *key != "..")
                 .map(|x| x.to_string())
                 .collect::<Vec<String>>();
 
@@ -143,19 +516,6 @@ impl Router {
             })
         }
     }
-
-    async fn static_dir_file_handler(ctx: Context) -> ContextResult {
-        let relative_path = ctx
-            .uri()
-            .path()
-            .split('/')
-            .filter(|key| !key.is_empty())
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>();
-
-        ctx.build(Response::ok().file(&relative_path.join("/")).await)
-            .ok()
-    }
 }
 
 #[cfg(test)]
@@ -264,6 +624,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn router_head_test() {
+        let mut router = Router::new();
+
+        router.get("router/test", handler);
+        router.get("router/explicit", handler);
+        router.head("router/explicit", handler);
+
+        let result = router.search_route("router/test").unwrap();
+        let route_value = result.get_route(&Method::HEAD).unwrap();
+
+        // Fall back to GET route
+        assert_eq!(route_value.method, Method::GET);
+
+        let result = router.search_route("router/explicit").unwrap();
+        let route_value = result.get_route(&Method::HEAD).unwrap();
+
+        assert_eq!(route_value.method, Method::HEAD);
+    }
+
+    #[test]
+    fn router_options_test() {
+        let mut router = Router::new();
+
+        router.post("router/test", handler);
+        router.options("router/explicit", handler);
+
+        let result = router.search_route("router/test").unwrap();
+
+        assert!(result.get_route(&Method::OPTIONS).is_none());
+        assert_eq!(
+            result.get_allowed_methods(),
+            vec![Method::OPTIONS, Method::POST]
+        );
+
+        let result = router.search_route("router/explicit").unwrap();
+        let route_value = result.get_route(&Method::OPTIONS).unwrap();
+
+        assert_eq!(route_value.method, Method::OPTIONS);
+        assert_eq!(result.get_allowed_methods(), vec![Method::OPTIONS]);
+    }
+
+    #[test]
+    fn router_custom_method_test() {
+        let mut router = Router::new();
+        let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+        let mkcol = Method::from_bytes(b"MKCOL").unwrap();
+
+        router.route(propfind.clone(), "router/test", handler);
+        router.route(mkcol.clone(), "router/test", handler);
+
+        let result = router.search_route("router/test").unwrap();
+
+        assert_eq!(result.get_route(&propfind).unwrap().method, propfind);
+        assert_eq!(result.get_route(&mkcol).unwrap().method, mkcol);
+        assert!(result.get_route(&Method::GET).is_none());
+    }
+
+    #[test]
+    fn router_any_test() {
+        let mut router = Router::new();
+        let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+
+        router.get("router/test", handler);
+        router.any("router/test", handler);
+        router.any("router/any", handler);
+
+        let result = router.search_route("router/test").unwrap();
+
+        assert_eq!(result.get_route(&Method::GET).unwrap().method, Method::GET);
+        assert_eq!(result.get_route(&Method::POST).unwrap().method, "*");
+        assert_eq!(result.get_route(&propfind).unwrap().method, "*");
+
+        let result = router.search_route("router/any").unwrap();
+
+        assert_eq!(result.get_route(&Method::HEAD).unwrap().method, "*");
+        assert_eq!(result.get_route(&Method::DELETE).unwrap().method, "*");
+    }
+
+    #[should_panic]
+    #[test]
+    fn router_duplicate_any_test() {
+        let mut router = Router::new();
+
+        router.any("router/test", handler);
+        router.any("router/test", handler);
+    }
+
     #[test]
     fn router_root_middleware_test() {
         let mut router = Router::new();
@@ -421,13 +869,399 @@ mod tests {
         router.get("router/test", handler);
     }
 
+    #[test]
+    fn router_static_param_priority_test() {
+        let mut router = Router::new();
+
+        router.get("router/:test", handler);
+        router.post("router/test", handler);
+
+        let result = router.search_route("router/test").unwrap();
+
+        assert!(result.get_route(&Method::POST).is_some());
+        assert!(result.get_route(&Method::GET).is_none());
+
+        let result = router.search_route("router/other").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_param("test"), Some("other"));
+    }
+
     #[should_panic]
     #[test]
     fn router_ambiguous_path_test() {
         let mut router = Router::new();
 
         router.get("router/:test", handler);
-        router.get("router/test", handler);
+        router.get("router/:other", handler);
+    }
+
+    #[test]
+    fn router_try_register_test() {
+        let mut router = Router::new();
+
+        router.try_get("router/test", handler).unwrap();
+        router.try_post("router/test", handler).unwrap();
+
+        assert_eq!(
+            router.try_get("router/test", handler).err(),
+            Some(RouteError::DuplicateMethod {
+                method: Method::GET,
+                path: "router/test".to_string()
+            })
+        );
+
+        assert!(matches!(
+            router.try_use_service_to("router/*/test", Logger::new()),
+            Err(RouteError::InvalidWildcard { .. })
+        ));
+
+        let mut sub_router = Router::new();
+        sub_router.get("list", handler);
+
+        assert_eq!(
+            router.try_use_router("router", sub_router),
+            Err(RouteError::SubRouterConflict {
+                path: "router".to_string()
+            })
+        );
+
+        let result = router.search_route("router/test").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert!(result.get_route(&Method::POST).is_some());
+    }
+
+    #[test]
+    fn router_url_for_test() {
+        let mut router = Router::new();
+        let mut admin_router = Router::new();
+        let mut user_router = Router::new();
+
+        router.get("/", handler).name("home");
+        router.any("/proxy/*path", handler).name("proxy");
+        router.use_static_to("/assets", "assets");
+
+        user_router.get("/:id<int>", handler).name("user");
+        user_router
+            .get("/:id<int>/files/:name.:ext", handler)
+            .name("user_file");
+        user_router
+            .post("/:id<int>/posts/:slug?", handler)
+            .name("user_post");
+
+        admin_router.use_router("/users", user_router);
+        router.use_router("/admin", admin_router);
+
+        assert_eq!(router.url_for("home", &[]).unwrap(), "/");
+        assert_eq!(
+            router.url_for("user", &[("id", "12")]).unwrap(),
+            "/admin/users/12"
+        );
+        assert_eq!(
+            router
+                .url_for(
+                    "user_file",
+                    &[("id", "12"), ("name", "annual report"), ("ext", "pdf")]
+                )
+                .unwrap(),
+            "/admin/users/12/files/annual%20report.pdf"
+        );
+        assert_eq!(
+            router.url_for("user_post", &[("id", "12")]).unwrap(),
+            "/admin/users/12/posts"
+        );
+        assert_eq!(
+            router
+                .url_for("user_post", &[("id", "12"), ("slug", "hello?")])
+                .unwrap(),
+            "/admin/users/12/posts/hello%3F"
+        );
+        assert_eq!(
+            router
+                .url_for("proxy", &[("path", "api/v1/users list")])
+                .unwrap(),
+            "/proxy/api/v1/users%20list"
+        );
+
+        // The generated url routes back to the named route
+        let url = router
+            .url_for(
+                "user_file",
+                &[("id", "12"), ("name", "report"), ("ext", "pdf")],
+            )
+            .unwrap();
+        let result = router.search_route(&url).unwrap();
+
+        assert_eq!(
+            result.get_route(&Method::GET).unwrap().name.as_deref(),
+            Some("user_file")
+        );
+
+        assert!(router.url_for("unknown", &[]).is_err());
+        assert!(router.url_for("user", &[]).is_err());
+        assert!(router.url_for("user", &[("id", "abc")]).is_err());
+    }
+
+    #[test]
+    fn router_url_for_static_key_test() {
+        let mut router = Router::new();
+
+        router.get("/ノーマル/:name", handler).name("normal");
+        router.get("/caf%C3%A9/:name", handler).name("cafe");
+
+        let url = router.url_for("normal", &[("name", "a b")]).unwrap();
+
+        assert_eq!(url, "/ノーマル/a%20b");
+
+        let url = router.url_for("cafe", &[("name", "a b")]).unwrap();
+
+        assert_eq!(url, "/caf%C3%A9/a%20b");
+
+        // The static keys are emitted as registered, so the urls route back to the named routes
+        for name in ["normal", "cafe"] {
+            let url = router.url_for(name, &[("name", "a b")]).unwrap();
+            let result = router.search_route(&url).unwrap();
+
+            assert_eq!(
+                result.get_route(&Method::GET).unwrap().name.as_deref(),
+                Some(name)
+            );
+            assert_eq!(result.get_param("name"), Some("a%20b"));
+        }
+    }
+
+    #[test]
+    fn router_url_for_with_query_test() {
+        #[derive(serde::Serialize)]
+        struct Query {
+            page: i32,
+            q: Option<String>,
+        }
+
+        let mut router = Router::new();
+
+        router.get("/search/:kind", handler).name("search");
+
+        let query = Query {
+            page: 2,
+            q: Some("rust & web".to_string()),
+        };
+
+        assert_eq!(
+            router
+                .url_for_with_query("search", &[("kind", "posts")], &query)
+                .unwrap(),
+            "/search/posts?page=2&q=rust+%26+web"
+        );
+
+        let query = Query { page: 1, q: None };
+
+        assert_eq!(
+            router
+                .url_for_with_query("search", &[("kind", "posts")], &query)
+                .unwrap(),
+            "/search/posts?page=1"
+        );
+    }
+
+    #[test]
+    fn router_duplicate_name_test() {
+        let mut router = Router::new();
+        let mut sub_router = Router::new();
+
+        router.get("/users", handler).name("users");
+
+        assert_eq!(
+            router
+                .try_post("/users", handler)
+                .unwrap()
+                .try_name("users")
+                .err(),
+            Some(RouteError::DuplicateName {
+                name: "users".to_string(),
+                path: "/users".to_string()
+            })
+        );
+
+        sub_router.get("/list", handler).name("users");
+
+        assert_eq!(
+            router.try_use_router("/admin", sub_router),
+            Err(RouteError::DuplicateName {
+                name: "users".to_string(),
+                path: "/admin".to_string()
+            })
+        );
+        assert!(router.search_route("/admin/list").is_none());
+    }
+
+    #[test]
+    fn router_url_for_trailing_slash_test() {
+        let mut router = Router::new();
+        let mut sub_router = Router::new();
+
+        router.get("/users/", handler).name("users");
+        router.get("/users/:id/:tab?/", handler).name("user");
+
+        sub_router.get("/", handler).name("admin");
+        sub_router.get("/*path", handler).name("admin_file");
+
+        router.use_router("/admin/", sub_router);
+
+        assert_eq!(router.url_for("users", &[]).unwrap(), "/users/");
+        assert_eq!(router.url_for("user", &[("id", "1")]).unwrap(), "/users/1/");
+        assert_eq!(router.url_for("admin", &[]).unwrap(), "/admin");
+        assert_eq!(
+            router.url_for("admin_file", &[("path", "a/b")]).unwrap(),
+            "/admin/a/b"
+        );
+    }
+
+    #[test]
+    fn router_inherit_middlewares_test() {
+        let mut router = Router::new();
+        let mut host_router = Router::new();
+
+        router.use_service(Logger::new());
+        router.use_service(Logger::new());
+        router.get("/", handler);
+
+        host_router.use_service(Logger::new());
+        host_router.get("/users", handler);
+        host_router.inherit_middlewares(&router);
+
+        let result = host_router.search_route("/users").unwrap();
+
+        assert_eq!(result.get_middlewares().len(), 3);
+        assert!(host_router
+            .search_route("/")
+            .unwrap()
+            .get_route(&Method::GET)
+            .is_none());
+    }
+
+    #[test]
+    fn router_inherit_settings_test() {
+        let mut router = Router::new();
+        let mut host_router = Router::new();
+
+        router.set_trailing_slash(TrailingSlash::MovedPermanently);
+        router.set_case_insensitive(true);
+
+        host_router.get("/users", handler);
+
+        assert!(host_router.search_route("/USERS").is_none());
+
+        host_router.inherit_settings(&router);
+
+        assert_eq!(
+            host_router.trailing_slash(),
+            TrailingSlash::MovedPermanently
+        );
+        assert!(host_router.search_route("/USERS").is_some());
+    }
+
+    #[test]
+    fn router_guard_registration_test() {
+        let mut router = Router::new();
+
+        router
+            .get("/users", handler)
+            .guard(guard::header("X-API-Version", "2"));
+        router
+            .get("/users", handler)
+            .guard(guard::accept("text/html"));
+        router.get("/users", handler);
+
+        assert_eq!(
+            router.try_get("/users", handler).err(),
+            Some(RouteError::DuplicateMethod {
+                method: Method::GET,
+                path: "/users".to_string()
+            })
+        );
+
+        router.post("/posts", handler);
+
+        assert_eq!(
+            router
+                .try_post("/posts", handler)
+                .map(|builder| builder.guard(guard::content_type("application/json")))
+                .err(),
+            Some(RouteError::DuplicateMethod {
+                method: Method::POST,
+                path: "/posts".to_string()
+            })
+        );
+
+        let guards = router
+            .search_route("/users")
+            .unwrap()
+            .get_resource()
+            .routes()
+            .map(|route| route.guards.len())
+            .collect::<Vec<usize>>();
+
+        assert_eq!(guards, vec![1, 1, 0]);
+        assert_eq!(router.routes().count(), 4);
+    }
+
+    #[test]
+    fn router_routes_test() {
+        let mut router = Router::new();
+        let mut sub_router = Router::new();
+
+        router.use_service(Logger::new());
+        router.get("/", handler).name("index");
+        router.post("/users", handler);
+        router.get("/users", handler).name("users");
+        router.any("/users", handler).with(Logger::new());
+
+        sub_router.use_service(Logger::new());
+        sub_router
+            .get("/users/:id<int>", handler)
+            .name("admin_user");
+
+        router.use_router("/admin", sub_router);
+
+        let routes = router
+            .routes()
+            .map(|route| {
+                (
+                    route.method.to_string(),
+                    route.path,
+                    route.middleware_count,
+                    route.name,
+                )
+            })
+            .collect::<Vec<(String, String, usize, Option<String>)>>();
+
+        assert_eq!(
+            routes,
+            vec![
+                (
+                    "GET".to_string(),
+                    "/".to_string(),
+                    1,
+                    Some("index".to_string())
+                ),
+                (
+                    "GET".to_string(),
+                    "/users".to_string(),
+                    1,
+                    Some("users".to_string())
+                ),
+                ("POST".to_string(), "/users".to_string(), 1, None),
+                ("*".to_string(), "/users".to_string(), 2, None),
+                (
+                    "GET".to_string(),
+                    "/admin/users/:id<int>".to_string(),
+                    2,
+                    Some("admin_user".to_string())
+                ),
+            ]
+        );
     }
 
     #[should_panic]
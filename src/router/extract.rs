@@ -0,0 +1,173 @@
+//! Extractors taking the arguments of the handler functions out of the context
+//!
+//! The extractors run in the order of the arguments, the body can only be taken
+//! by one of them such as `Json` or `Form`.
+//! Failed extraction is answered with the rejection of the extractor instead of calling the handler,
+//! e.g. 400 Bad Request for the body which cannot be parsed
+//!
+//! # Example
+//! ```
+//! use obsidian::{router::{extract::{Json, Params, State}, Router}, StatusCode};
+//! use serde::Deserialize;
+//!
+//! #[derive(Clone)]
+//! struct AppState {
+//!     db_connection_string: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct UserParams {
+//!     id: i32,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let mut router = Router::new();
+//!
+//! router.put(
+//!     "/users/:id",
+//!     |Params(params): Params<UserParams>, Json(user): Json<User>, State(state): State<AppState>| async move {
+//!         format!("{} {} {}", params.id, user.name, state.db_connection_string)
+//!     },
+//! );
+//! router.get("/health", |_: State<AppState>| async { StatusCode::OK });
+//! ```
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+
+use std::fmt::Display;
+
+use crate::context::Context;
+use crate::router::{Responder, Response};
+use crate::{HeaderMap, Method, StatusCode};
+
+/// Type which can be extracted from the context as the argument of the handler function
+#[async_trait]
+pub trait FromContext: Sized {
+    /// Response sent to the client when the extraction fails
+    type Rejection: Responder;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Self::Rejection>;
+}
+
+/// Json body deserialized into `T`, see [`Context::json`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Json<T>(pub T);
+
+/// Form body deserialized into `T`, see [`Context::form`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Form<T>(pub T);
+
+/// Query string deserialized into `T`, see [`Context::query_params`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query<T>(pub T);
+
+/// Route params deserialized into `T`, see [`Context::params`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Params<T>(pub T);
+
+/// Clone of the app state set through `App::set_app_state`
+#[derive(Clone, Debug, PartialEq)]
+pub struct State<T>(pub T);
+
+#[async_trait]
+impl<T> FromContext for Json<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Rejection = Response;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        ctx.json()
+            .await
+            .map(Json)
+            .map_err(|err| rejection(StatusCode::BAD_REQUEST, err))
+    }
+}
+
+#[async_trait]
+impl<T> FromContext for Form<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Rejection = Response;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        ctx.form()
+            .await
+            .map(Form)
+            .map_err(|err| rejection(StatusCode::BAD_REQUEST, err))
+    }
+}
+
+#[async_trait]
+impl<T> FromContext for Query<T>
+where
+    T: DeserializeOwned,
+{
+    type Rejection = Response;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        ctx.query_params()
+            .map(Query)
+            .map_err(|err| rejection(StatusCode::BAD_REQUEST, err))
+    }
+}
+
+#[async_trait]
+impl<T> FromContext for Params<T>
+where
+    T: DeserializeOwned,
+{
+    type Rejection = Response;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        ctx.params()
+            .map(Params)
+            .map_err(|err| rejection(StatusCode::BAD_REQUEST, err))
+    }
+}
+
+#[async_trait]
+impl<T> FromContext for State<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = Response;
+
+    /// Missing state is the mistake of the server, answered with 500 Internal Server Error.
+    /// The type of the state is logged without leaking into the body
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        ctx.get::<T>().cloned().map(State).ok_or_else(|| {
+            eprintln!("app state {} is not set", std::any::type_name::<T>());
+
+            rejection(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
+        })
+    }
+}
+
+#[async_trait]
+impl FromContext for HeaderMap {
+    type Rejection = Response;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        Ok(ctx.headers().clone())
+    }
+}
+
+#[async_trait]
+impl FromContext for Method {
+    type Rejection = Response;
+
+    async fn from_context(ctx: &mut Context) -> Result<Self, Response> {
+        Ok(ctx.method().clone())
+    }
+}
+
+fn rejection(status: StatusCode, err: impl Display) -> Response {
+    err.to_string().respond_to().set_status(status)
+}
@@ -0,0 +1,197 @@
+//! Guards choosing between the routes registered with the same path and method
+//!
+//! # Example
+//! ```
+//! use obsidian::{context::Context, router::{guard, Router}};
+//!
+//! let mut router = Router::new();
+//!
+//! router
+//!     .post("/users", |ctx: Context| async { ctx.build("json").ok() })
+//!     .guard(guard::content_type("application/json"));
+//! router
+//!     .post("/users", |ctx: Context| async { ctx.build("v2").ok() })
+//!     .guard(guard::header("X-API-Version", "2"))
+//!     .guard(|ctx: &Context| ctx.uri().query().is_none());
+//! ```
+
+use hyper::header::{self, HeaderName};
+
+use crate::context::Context;
+use crate::StatusCode;
+
+/// Predicate over the request deciding whether the route serves it
+pub trait Guard: Send + Sync + 'static {
+    fn check(&self, ctx: &Context) -> bool;
+
+    /// Status of the response when no route passes because of this guard
+    fn rejection_status(&self) -> StatusCode {
+        StatusCode::NOT_FOUND
+    }
+}
+
+impl<T> Guard for T
+where
+    T: Fn(&Context) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, ctx: &Context) -> bool {
+        (self)(ctx)
+    }
+}
+
+/// Guard passing the requests with the header equal to the value.
+/// Panic if the name is not a valid header name
+pub fn header(name: &str, value: &str) -> impl Guard {
+    HeaderGuard {
+        name: HeaderName::from_bytes(name.as_bytes())
+            .unwrap_or_else(|_| panic!("Invalid header name '{}'", name)),
+        value: value.to_string(),
+    }
+}
+
+/// Guard passing the requests whose `Content-Type` is the media type, parameters such as charset are ignored.
+/// Rejected requests are answered with 415 Unsupported Media Type
+pub fn content_type(media_type: &str) -> impl Guard {
+    ContentTypeGuard {
+        media_type: media_type.to_ascii_lowercase(),
+    }
+}
+
+/// Guard passing the requests whose `Accept` header covers the media type, including `*/*` and `type/*`.
+/// Requests without `Accept` header accept any media type
+pub fn accept(media_type: &str) -> impl Guard {
+    AcceptGuard {
+        media_type: media_type.to_ascii_lowercase(),
+    }
+}
+
+struct HeaderGuard {
+    name: HeaderName,
+    value: String,
+}
+
+impl Guard for HeaderGuard {
+    fn check(&self, ctx: &Context) -> bool {
+        ctx.headers()
+            .get_all(&self.name)
+            .iter()
+            .any(|value| value == self.value.as_str())
+    }
+}
+
+struct ContentTypeGuard {
+    media_type: String,
+}
+
+impl Guard for ContentTypeGuard {
+    fn check(&self, ctx: &Context) -> bool {
+        ctx.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| media_type_of(value).eq_ignore_ascii_case(&self.media_type))
+    }
+
+    fn rejection_status(&self) -> StatusCode {
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    }
+}
+
+struct AcceptGuard {
+    media_type: String,
+}
+
+impl Guard for AcceptGuard {
+    fn check(&self, ctx: &Context) -> bool {
+        let mut values = ctx
+            .headers()
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(media_type_of)
+            .peekable();
+
+        if values.peek().is_none() {
+            return true;
+        }
+
+        let main_type = self.media_type.split('/').next().unwrap_or_default();
+
+        values.any(|accepted| {
+            let accepted = accepted.to_ascii_lowercase();
+
+            accepted == "*/*"
+                || accepted == self.media_type
+                || accepted
+                    .strip_suffix("/*")
+                    .is_some_and(|accepted_type| accepted_type == main_type)
+        })
+    }
+}
+
+/// Media type of the header value without parameters, such as `text/html` of `text/html; charset=utf-8`
+fn media_type_of(value: &str) -> &str {
+    value.split(';').next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Request};
+    use std::collections::HashMap;
+
+    fn context_with_headers(headers: &[(&str, &str)]) -> Context {
+        let mut request = Request::builder();
+
+        for (name, value) in headers.iter() {
+            request = request.header(*name, *value);
+        }
+
+        Context::new(request.body(Body::from("")).unwrap(), HashMap::default())
+    }
+
+    #[test]
+    fn header_guard_test() {
+        let guard = header("X-API-Version", "2");
+
+        assert!(guard.check(&context_with_headers(&[("x-api-version", "2")])));
+        assert!(!guard.check(&context_with_headers(&[("X-API-Version", "1")])));
+        assert!(!guard.check(&context_with_headers(&[])));
+        assert_eq!(guard.rejection_status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn content_type_guard_test() {
+        let guard = content_type("application/json");
+
+        assert!(guard.check(&context_with_headers(&[(
+            "Content-Type",
+            "Application/JSON; charset=utf-8"
+        )])));
+        assert!(!guard.check(&context_with_headers(&[("Content-Type", "text/plain")])));
+        assert!(!guard.check(&context_with_headers(&[])));
+        assert_eq!(guard.rejection_status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[test]
+    fn accept_guard_test() {
+        let guard = accept("text/html");
+
+        assert!(guard.check(&context_with_headers(&[])));
+        assert!(guard.check(&context_with_headers(&[(
+            "Accept",
+            "application/json, text/html;q=0.9"
+        )])));
+        assert!(guard.check(&context_with_headers(&[("Accept", "text/*")])));
+        assert!(guard.check(&context_with_headers(&[("Accept", "*/*")])));
+        assert!(!guard.check(&context_with_headers(&[("Accept", "application/json")])));
+        assert!(!guard.check(&context_with_headers(&[("Accept", "image/*")])));
+    }
+
+    #[test]
+    fn closure_guard_test() {
+        let guard = |ctx: &Context| ctx.uri().query().is_some();
+
+        assert!(!Guard::check(&guard, &context_with_headers(&[])));
+    }
+}
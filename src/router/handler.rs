@@ -1,8 +1,12 @@
 use crate::context::Context;
 use crate::error::ObsidianError;
 
+use super::extract::FromContext;
+use super::Responder;
+
 use async_trait::async_trait;
 use std::future::Future;
+use std::marker::PhantomData;
 
 pub type ContextResult<T = ObsidianError> = Result<Context, T>;
 
@@ -21,3 +25,123 @@ where
         (self)(ctx).await
     }
 }
+
+/// Conversion into the `Handler` when the route is registered.
+/// `Args` tells apart the handlers returning `ContextResult`, the functions taking the `Context`
+/// and returning a `Responder`, and the functions taking the extractors
+pub trait IntoHandler<Args>: Send + Sync + 'static {
+    type Handler: Handler;
+
+    fn into_handler(self) -> Self::Handler;
+}
+
+impl<H: Handler> IntoHandler<Context> for H {
+    type Handler = H;
+
+    fn into_handler(self) -> H {
+        self
+    }
+}
+
+/// Handler calling the function with the context, building the response from the `Responder` it returns.
+/// `Result` and `Option` are responders as well, so that `?` can be used in the function.
+/// The extensions are kept for the middlewares, so the function does not see them,
+/// take the extractors such as `State` instead to read them
+pub struct ResponderHandler<F> {
+    handler: F,
+}
+
+#[async_trait]
+impl<F, Fut, R> Handler for ResponderHandler<F>
+where
+    F: Fn(Context) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Responder,
+{
+    async fn call(&self, mut ctx: Context) -> ContextResult {
+        // The function consumes the context, the response and the extensions are carried back
+        // to the middlewares by the split context
+        let response_ctx = ctx.split_head();
+        let res = (self.handler)(ctx).await;
+
+        response_ctx.build(res).ok()
+    }
+}
+
+impl<F, Fut, R> IntoHandler<(Context,)> for F
+where
+    F: Fn(Context) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Responder,
+{
+    type Handler = ResponderHandler<F>;
+
+    fn into_handler(self) -> Self::Handler {
+        ResponderHandler { handler: self }
+    }
+}
+
+/// Handler calling the function with the arguments extracted from the context.
+/// The response is built from the `Responder` returned by the function,
+/// or from the rejection of the first extractor which fails
+pub struct ExtractorHandler<F, Args> {
+    handler: F,
+    _args: PhantomData<fn() -> Args>,
+}
+
+macro_rules! extractor_handler_impl {
+    ($($extractor:ident),+) => {
+        #[async_trait]
+        impl<F, Fut, R, $($extractor),+> Handler for ExtractorHandler<F, ($($extractor,)+)>
+        where
+            F: Fn($($extractor),+) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = R> + Send + 'static,
+            R: Responder,
+            $($extractor: FromContext + Send + 'static,)+
+        {
+            #[allow(non_snake_case)]
+            async fn call(&self, mut ctx: Context) -> ContextResult {
+                $(
+                    let $extractor = match $extractor::from_context(&mut ctx).await {
+                        Ok(value) => value,
+                        Err(rejection) => return ctx.build(rejection).ok(),
+                    };
+                )+
+
+                let res = (self.handler)($($extractor),+).await;
+
+                ctx.build(res).ok()
+            }
+        }
+
+        impl<F, Fut, R, $($extractor),+> IntoHandler<($($extractor,)+)> for F
+        where
+            F: Fn($($extractor),+) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = R> + Send + 'static,
+            R: Responder,
+            $($extractor: FromContext + Send + 'static,)+
+        {
+            type Handler = ExtractorHandler<F, ($($extractor,)+)>;
+
+            fn into_handler(self) -> Self::Handler {
+                ExtractorHandler {
+                    handler: self,
+                    _args: PhantomData,
+                }
+            }
+        }
+    };
+}
+
+extractor_handler_impl!(E1);
+extractor_handler_impl!(E1, E2);
+extractor_handler_impl!(E1, E2, E3);
+extractor_handler_impl!(E1, E2, E3, E4);
+extractor_handler_impl!(E1, E2, E3, E4, E5);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6, E7);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6, E7, E8);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6, E7, E8, E9);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6, E7, E8, E9, E10);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6, E7, E8, E9, E10, E11);
+extractor_handler_impl!(E1, E2, E3, E4, E5, E6, E7, E8, E9, E10, E11, E12);
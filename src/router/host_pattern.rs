@@ -0,0 +1,160 @@
+use super::route_trie::RouteParams;
+use super::segment_pattern::SegmentPattern;
+use crate::error::RouteError;
+
+/// Label of the host pattern separated by '.'
+#[derive(Clone, Debug)]
+enum Label {
+    Static(String),
+    Pattern(SegmentPattern),
+}
+
+/// Pattern of the host served by a separate router, such as `api.example.com` or `:tenant.example.com`
+///
+/// Labels are matched case-insensitively, params must be within a label and cannot be optional.
+/// Constraints cannot contain '.' since the host is split into labels before matching
+#[derive(Clone, Debug)]
+pub struct HostPattern {
+    host: String,
+    labels: Vec<Label>,
+}
+
+impl HostPattern {
+    pub fn parse(host: &str) -> Result<Self, RouteError> {
+        let invalid_host = || RouteError::InvalidHost {
+            host: host.to_string(),
+        };
+
+        let labels = host
+            .split('.')
+            .map(|label| {
+                if label.is_empty() {
+                    return Err(invalid_host());
+                }
+
+                if !label.contains(':') {
+                    return Ok(Label::Static(label.to_ascii_lowercase()));
+                }
+
+                match SegmentPattern::parse(label) {
+                    Ok(pattern) if !pattern.is_optional() => Ok(Label::Pattern(pattern)),
+                    _ => Err(invalid_host()),
+                }
+            })
+            .collect::<Result<Vec<Label>, RouteError>>()?;
+
+        Ok(HostPattern {
+            host: host.to_string(),
+            labels,
+        })
+    }
+
+    /// The pattern as registered
+    pub fn as_str(&self) -> &str {
+        &self.host
+    }
+
+    /// Check whether the pattern consists of static labels only
+    pub fn is_static(&self) -> bool {
+        self.labels
+            .iter()
+            .all(|label| matches!(label, Label::Static(_)))
+    }
+
+    /// Match the lowercase host without port and push the captured params
+    /// The params are left untouched if the host does not match
+    pub fn match_host<'r, 'p>(&'r self, host: &'p str, params: &mut RouteParams<'r, 'p>) -> bool {
+        let params_len = params.len();
+        let mut labels = host.split('.');
+
+        for label in self.labels.iter() {
+            let matched = match (label, labels.next()) {
+                (Label::Static(key), Some(segment)) => key == segment,
+                (Label::Pattern(pattern), Some(segment)) => pattern.match_segment(segment, params),
+                (_, None) => false,
+            };
+
+            if !matched {
+                params.truncate(params_len);
+                return false;
+            }
+        }
+
+        if labels.next().is_some() {
+            params.truncate(params_len);
+            return false;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures<'r, 'p>(
+        pattern: &'r HostPattern,
+        host: &'p str,
+    ) -> Option<Vec<(&'r str, &'p str)>> {
+        let mut params = RouteParams::new();
+
+        if pattern.match_host(host, &mut params) {
+            Some(params.to_vec())
+        } else {
+            assert!(params.is_empty());
+            None
+        }
+    }
+
+    #[test]
+    fn host_pattern_parse_test() {
+        assert!(HostPattern::parse("api.example.com").unwrap().is_static());
+        assert!(!HostPattern::parse(":tenant.example.com")
+            .unwrap()
+            .is_static());
+
+        for host in [
+            "",
+            "api..example.com",
+            ":tenant?.example.com",
+            ":.example.com",
+            ":id<\\d+.example.com",
+            r":id<\d+\.\d+>.example.com",
+        ] {
+            assert_eq!(
+                HostPattern::parse(host).err(),
+                Some(RouteError::InvalidHost {
+                    host: host.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn host_pattern_match_test() {
+        let pattern = HostPattern::parse("API.example.com").unwrap();
+
+        assert_eq!(captures(&pattern, "api.example.com"), Some(vec![]));
+        assert_eq!(captures(&pattern, "www.example.com"), None);
+        assert_eq!(captures(&pattern, "api.example.com.my"), None);
+        assert_eq!(captures(&pattern, "example.com"), None);
+
+        let pattern = HostPattern::parse(":tenant.example.com").unwrap();
+
+        assert_eq!(
+            captures(&pattern, "acme.example.com"),
+            Some(vec![("tenant", "acme")])
+        );
+        assert_eq!(captures(&pattern, "acme.example.org"), None);
+        assert_eq!(captures(&pattern, "a.b.example.com"), None);
+
+        let pattern = HostPattern::parse(r"shop-:id<\d+>.:region.example.com").unwrap();
+
+        assert_eq!(
+            captures(&pattern, "shop-12.eu.example.com"),
+            Some(vec![("id", "12"), ("region", "eu")])
+        );
+        assert_eq!(captures(&pattern, "shop-ab.eu.example.com"), None);
+    }
+}
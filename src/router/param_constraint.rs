@@ -0,0 +1,118 @@
+use regex::Regex;
+
+/// Constraint restricting the segments matched by a route param, written as `:name<constraint>`
+///
+/// `int` and `uuid` are built in, any other constraint is a regex which must match the whole segment.
+/// Constraints are checked against the raw segment of the path before percent-decoding,
+/// e.g. `<[a-z ]+>` does not match `a%20b`
+#[derive(Clone, Debug)]
+pub enum ParamConstraint {
+    /// Digits with an optional leading '-' within the range of `i64`
+    Int,
+    /// Hyphenated hexadecimal uuid such as `67e55044-10b1-426f-9247-bb680e5fe0c8`
+    Uuid,
+    Regex(Regex),
+}
+
+impl ParamConstraint {
+    pub fn new(constraint: &str) -> Result<Self, regex::Error> {
+        match constraint {
+            "int" => Ok(ParamConstraint::Int),
+            "uuid" => Ok(ParamConstraint::Uuid),
+            _ => Regex::new(&format!("^(?:{})$", constraint)).map(ParamConstraint::Regex),
+        }
+    }
+
+    /// Check whether the segment satisfies the constraint
+    pub fn is_match(&self, segment: &str) -> bool {
+        match self {
+            ParamConstraint::Int => {
+                let digits = segment.strip_prefix('-').unwrap_or(segment);
+
+                !digits.is_empty()
+                    && digits.bytes().all(|byte| byte.is_ascii_digit())
+                    && segment.parse::<i64>().is_ok()
+            }
+            ParamConstraint::Uuid => {
+                segment.len() == 36
+                    && segment.bytes().enumerate().all(|(pos, byte)| match pos {
+                        8 | 13 | 18 | 23 => byte == b'-',
+                        _ => byte.is_ascii_hexdigit(),
+                    })
+            }
+            ParamConstraint::Regex(regex) => regex.is_match(segment),
+        }
+    }
+}
+
+impl PartialEq for ParamConstraint {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ParamConstraint::Int, ParamConstraint::Int) => true,
+            (ParamConstraint::Uuid, ParamConstraint::Uuid) => true,
+            (ParamConstraint::Regex(a), ParamConstraint::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_constraint_test() {
+        let int = ParamConstraint::new("int").unwrap();
+
+        assert!(int.is_match("123"));
+        assert!(int.is_match("-42"));
+        assert!(!int.is_match("-"));
+        assert!(!int.is_match("12a"));
+        assert!(!int.is_match(""));
+        assert!(int.is_match("9223372036854775807"));
+        assert!(int.is_match("-9223372036854775808"));
+        assert!(!int.is_match("9223372036854775808"));
+        assert!(!int.is_match("99999999999999999999"));
+        assert!(!int.is_match("+42"));
+        assert!(!int.is_match("-+42"));
+
+        let uuid = ParamConstraint::new("uuid").unwrap();
+
+        assert!(uuid.is_match("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(uuid.is_match("67E55044-10B1-426F-9247-BB680E5FE0C8"));
+        assert!(!uuid.is_match("67e5504410b1426f9247bb680e5fe0c8"));
+        assert!(!uuid.is_match("67e55044-10b1-426f-9247-bb680e5fe0cg"));
+    }
+
+    #[test]
+    fn regex_constraint_test() {
+        let constraint = ParamConstraint::new(r"\d+").unwrap();
+
+        assert!(constraint.is_match("123"));
+        assert!(!constraint.is_match("a123"));
+        assert!(!constraint.is_match("123a"));
+
+        let constraint = ParamConstraint::new("draft|published").unwrap();
+
+        assert!(constraint.is_match("draft"));
+        assert!(!constraint.is_match("drafts"));
+
+        assert!(ParamConstraint::new("(").is_err());
+    }
+
+    #[test]
+    fn constraint_eq_test() {
+        assert_eq!(
+            ParamConstraint::new("int").unwrap(),
+            ParamConstraint::new("int").unwrap()
+        );
+        assert_eq!(
+            ParamConstraint::new(r"\d+").unwrap(),
+            ParamConstraint::new(r"\d+").unwrap()
+        );
+        assert_ne!(
+            ParamConstraint::new("int").unwrap(),
+            ParamConstraint::new(r"\d+").unwrap()
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use serde::de::value::MapDeserializer;
 use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
 use serde::forward_to_deserialize_any;
 use serde::ser;
@@ -73,6 +74,200 @@ where
     Ok(t)
 }
 
+/// Parse the params into a user defined struct, the error names the param failed to parse
+pub(crate) fn from_params_map<'de, T, S: ::std::hash::BuildHasher>(
+    s: &'de HashMap<Cow<'de, str>, Cow<'de, [String]>, S>,
+) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut deserializer = FormDeserializer::from_cow_map(s.iter().peekable());
+    deserializer.name_failed_key = true;
+    T::deserialize(&mut deserializer)
+}
+
+/// Parse the json object merged with the params into a user defined struct.
+/// The params are parsed like form values, so that `"1"` can be deserialized into numeric field
+pub(crate) fn from_json_with_params<'de, T, S: ::std::hash::BuildHasher>(
+    body: serde_json::Map<String, serde_json::Value>,
+    params: &'de HashMap<String, String, S>,
+) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    let entries =
+        body.into_iter()
+            .filter(|(key, _)| !params.contains_key(key))
+            .map(|(key, value)| (key, MergedValue::Json(value)))
+            .chain(params.iter().map(|(key, value)| {
+                (key.clone(), MergedValue::Param(std::slice::from_ref(value)))
+            }));
+
+    T::deserialize(MapDeserializer::new(entries))
+}
+
+/// Value of the json object merged with the params
+enum MergedValue<'de> {
+    Json(serde_json::Value),
+    Param(&'de [String]),
+}
+
+macro_rules! forward_merged_value_impl {
+    ($($method:ident)*) => {$(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+                where V: de::Visitor<'de>
+            {
+                match self {
+                    MergedValue::Json(value) => value.$method(visitor).map_err(de::Error::custom),
+                    MergedValue::Param(input) => {
+                        (&mut FormValueDeserializer::new(input)).$method(visitor)
+                    }
+                }
+            }
+    )*}
+}
+
+impl<'de> IntoDeserializer<'de, Error> for MergedValue<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for MergedValue<'de> {
+    type Error = Error;
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            MergedValue::Json(value) => value
+                .deserialize_unit_struct(name, visitor)
+                .map_err(de::Error::custom),
+            MergedValue::Param(input) => {
+                (&mut FormValueDeserializer::new(input)).deserialize_unit_struct(name, visitor)
+            }
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            MergedValue::Json(value) => value
+                .deserialize_newtype_struct(name, visitor)
+                .map_err(de::Error::custom),
+            MergedValue::Param(input) => {
+                (&mut FormValueDeserializer::new(input)).deserialize_newtype_struct(name, visitor)
+            }
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            MergedValue::Json(value) => value
+                .deserialize_tuple(len, visitor)
+                .map_err(de::Error::custom),
+            MergedValue::Param(input) => {
+                (&mut FormValueDeserializer::new(input)).deserialize_tuple(len, visitor)
+            }
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            MergedValue::Json(value) => value
+                .deserialize_tuple_struct(name, len, visitor)
+                .map_err(de::Error::custom),
+            MergedValue::Param(input) => (&mut FormValueDeserializer::new(input))
+                .deserialize_tuple_struct(name, len, visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            MergedValue::Json(value) => value
+                .deserialize_struct(name, fields, visitor)
+                .map_err(de::Error::custom),
+            MergedValue::Param(input) => {
+                (&mut FormValueDeserializer::new(input)).deserialize_struct(name, fields, visitor)
+            }
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            MergedValue::Json(value) => value
+                .deserialize_enum(name, variants, visitor)
+                .map_err(de::Error::custom),
+            MergedValue::Param(input) => {
+                // Unit variant named by the param
+                visitor.visit_enum(input[0].clone().into_deserializer())
+            }
+        }
+    }
+
+    forward_merged_value_impl! {
+        deserialize_any
+        deserialize_bool
+        deserialize_u8
+        deserialize_u16
+        deserialize_u32
+        deserialize_u64
+        deserialize_i8
+        deserialize_i16
+        deserialize_i32
+        deserialize_i64
+        deserialize_f32
+        deserialize_f64
+        deserialize_char
+        deserialize_str
+        deserialize_string
+        deserialize_bytes
+        deserialize_byte_buf
+        deserialize_option
+        deserialize_unit
+        deserialize_seq
+        deserialize_map
+        deserialize_identifier
+        deserialize_ignored_any
+    }
+}
+
 /// Deserializer for merged hashmap forms.
 struct FormDeserializer<'de> {
     input: std::iter::Peekable<
@@ -82,6 +277,8 @@ struct FormDeserializer<'de> {
             std::borrow::Cow<'de, [String]>,
         >,
     >,
+    /// Name the key in the error of the value failed to parse
+    name_failed_key: bool,
 }
 
 macro_rules! from_string_forms_key_impl {
@@ -107,11 +304,14 @@ impl<'de> FormDeserializer<'de> {
             >,
         >,
     ) -> Self {
-        FormDeserializer { input }
+        FormDeserializer {
+            input,
+            name_failed_key: false,
+        }
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut FormDeserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut FormDeserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
@@ -394,8 +594,17 @@ impl<'de, 'a> MapAccess<'de> for FromMap<'a, 'de> {
     where
         V: DeserializeSeed<'de>,
     {
+        let name_failed_key = self.de.name_failed_key;
+
         match self.de.input.peek() {
-            Some(val) => seed.deserialize(&mut FormValueDeserializer::new(val.1)),
+            Some(val) => seed
+                .deserialize(&mut FormValueDeserializer::new(val.1))
+                .map_err(|err| match err {
+                    Error::Message(msg) if name_failed_key => {
+                        Error::Message(format!("Failed to parse {}: {}", val.0, msg))
+                    }
+                    err => err,
+                }),
             _ => Err(Error::NoneError),
         }
     }
@@ -472,10 +681,7 @@ mod tests {
             form_urlencoded::parse(buf.chunk())
                 .into_owned()
                 .for_each(|(key, val)| {
-                    parsed_form_map
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(val);
+                    parsed_form_map.entry(key).or_default().push(val);
                 });
             // Wrap vec with cow pointer
             parsed_form_map.iter().for_each(|(key, val)| {
@@ -509,10 +715,7 @@ mod tests {
             form_urlencoded::parse(buf.chunk())
                 .into_owned()
                 .for_each(|(key, val)| {
-                    parsed_form_map
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(val);
+                    parsed_form_map.entry(key).or_default().push(val);
                 });
             // Wrap vec with cow pointer
             parsed_form_map.iter().for_each(|(key, val)| {
@@ -543,10 +746,7 @@ mod tests {
             form_urlencoded::parse(buf.chunk())
                 .into_owned()
                 .for_each(|(key, val)| {
-                    parsed_form_map
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(val);
+                    parsed_form_map.entry(key).or_default().push(val);
                 });
             // Wrap vec with cow pointer
             parsed_form_map.iter().for_each(|(key, val)| {
@@ -577,10 +777,7 @@ mod tests {
             form_urlencoded::parse(buf.chunk())
                 .into_owned()
                 .for_each(|(key, val)| {
-                    parsed_form_map
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(val);
+                    parsed_form_map.entry(key).or_default().push(val);
                 });
             // Wrap vec with cow pointer
             parsed_form_map.iter().for_each(|(key, val)| {
@@ -614,10 +811,7 @@ mod tests {
             form_urlencoded::parse(buf.chunk())
                 .into_owned()
                 .for_each(|(key, val)| {
-                    parsed_form_map
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(val);
+                    parsed_form_map.entry(key).or_default().push(val);
                 });
             // Wrap vec with cow pointer
             parsed_form_map.iter().for_each(|(key, val)| {
@@ -631,17 +825,36 @@ mod tests {
 
             expected_result
                 .entry("field1".to_string())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(1);
             expected_result
                 .entry("field1".to_string())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(2);
             expected_result
                 .entry("field2".to_string())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(3);
             assert_eq!(actual_result, expected_result);
         })
     }
+
+    #[test]
+    fn test_deserialize_error_naming_the_param() {
+        let value = vec!["abc".to_string()];
+        let mut cow_map = HashMap::<Cow<str>, Cow<[String]>>::default();
+        cow_map.insert(Cow::from("field1"), Cow::from(&value));
+
+        let form_error = from_cow_map::<VecStruct, _>(&cow_map).unwrap_err();
+        let params_error = from_params_map::<VecStruct, _>(&cow_map).unwrap_err();
+
+        assert_eq!(
+            form_error,
+            Error::Message("invalid digit found in string".to_string())
+        );
+        assert_eq!(
+            params_error,
+            Error::Message("Failed to parse field1: invalid digit found in string".to_string())
+        );
+    }
 }
@@ -2,28 +2,127 @@ use hyper::Method;
 use std::collections::HashMap;
 
 use super::Route;
+use crate::context::Context;
+use crate::StatusCode;
 
 /// Resource acts as the intermidiate interface for interaction of routing data structure
 /// Resource is binding with the path and handling all of the request method for that path
-#[derive(Clone, Debug)]
+///
+/// Routes registered with the same method are checked in order, the first route whose guards pass
+/// serves the request
+#[derive(Clone, Debug, Default)]
 pub struct Resource {
-    route_map: HashMap<Method, Route>,
+    route_map: HashMap<Method, Vec<Route>>,
+    any_route: Option<Route>,
 }
 
-impl Default for Resource {
-    fn default() -> Self {
-        Resource {
-            route_map: HashMap::new(),
+impl Resource {
+    /// Add the route after the routes registered with the same method.
+    /// The route is given back if an existing route without guards would shadow it
+    pub fn add_route(&mut self, method: Method, route: Route) -> Result<(), Route> {
+        let routes = self.route_map.entry(method).or_default();
+
+        if routes.iter().any(|existing| existing.guards.is_empty()) {
+            return Err(route);
         }
+
+        routes.push(route);
+
+        Ok(())
     }
-}
 
-impl Resource {
-    pub fn add_route(&mut self, method: Method, route: Route) -> Option<Route> {
-        self.route_map.insert(method, route)
+    /// Set the route which handles any request method without its own route
+    pub fn add_any_route(&mut self, route: Route) -> Option<Route> {
+        self.any_route.replace(route)
     }
 
+    /// Get the first route for the request method regardless of the guards.
+    /// HEAD falls back to the GET route if it is not registered explicitly.
+    /// Any method route is used when no method specific route matches.
     pub fn get_route(&self, method: &Method) -> Option<&Route> {
-        self.route_map.get(&method)
+        self.get_candidates(method).next()
+    }
+
+    /// Get the first route for the request method whose guards pass, in the same fallback order as `get_route`.
+    /// Error with the rejection status of the first failing guard other than 404 Not Found if no route passes,
+    /// such as 415 Unsupported Media Type of the `content_type` guard, otherwise 404 Not Found
+    pub fn select_route(&self, method: &Method, ctx: &Context) -> Result<&Route, StatusCode> {
+        let mut status = StatusCode::NOT_FOUND;
+
+        for route in self.get_candidates(method) {
+            match route.guards.iter().find(|guard| !guard.check(ctx)) {
+                None => return Ok(route),
+                Some(guard) if status == StatusCode::NOT_FOUND => {
+                    status = guard.rejection_status();
+                }
+                Some(_) => {}
+            }
+        }
+
+        Err(status)
+    }
+
+    /// Get the last route registered exactly with the method, including the any method route
+    pub fn get_route_mut(&mut self, method: &Method) -> Option<&mut Route> {
+        match self
+            .route_map
+            .get_mut(method)
+            .and_then(|routes| routes.last_mut())
+        {
+            Some(route) => Some(route),
+            None => self
+                .any_route
+                .as_mut()
+                .filter(|route| route.method == method),
+        }
+    }
+
+    /// Get all of the routes registered for this path, including the any method route
+    pub fn routes(&self) -> impl Iterator<Item = &Route> {
+        self.route_map
+            .values()
+            .flatten()
+            .chain(self.any_route.iter())
+    }
+
+    /// Check whether no route is registered for this path
+    pub fn is_empty(&self) -> bool {
+        self.route_map.is_empty() && self.any_route.is_none()
+    }
+
+    /// Get all of the request methods registered for this path, sorted by name
+    pub fn get_methods(&self) -> Vec<&Method> {
+        let mut methods = self.route_map.keys().collect::<Vec<&Method>>();
+        methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        methods
+    }
+
+    /// Get the request methods allowed for this path, including HEAD and OPTIONS
+    /// which are handled automatically
+    pub fn get_allowed_methods(&self) -> Vec<Method> {
+        let mut methods = self.route_map.keys().cloned().collect::<Vec<Method>>();
+
+        if self.route_map.contains_key(&Method::GET) && !self.route_map.contains_key(&Method::HEAD)
+        {
+            methods.push(Method::HEAD);
+        }
+        if !self.route_map.contains_key(&Method::OPTIONS) {
+            methods.push(Method::OPTIONS);
+        }
+        methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        methods
+    }
+
+    /// Routes which may serve the request method in order
+    fn get_candidates(&self, method: &Method) -> impl Iterator<Item = &Route> {
+        match self.route_map.get(method) {
+            None if method == Method::HEAD => self.route_map.get(&Method::GET),
+            routes => routes,
+        }
+        .into_iter()
+        .flatten()
+        .chain(self.any_route.iter())
     }
 }
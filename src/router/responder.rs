@@ -2,6 +2,8 @@ use super::Response;
 use super::ResponseBody;
 use hyper::{header, StatusCode};
 
+use std::fmt::Display;
+
 pub trait Responder {
     fn respond_to(self) -> Response;
     fn with_status(self, status: StatusCode) -> Response
@@ -88,7 +90,11 @@ impl Responder for StatusCode {
     }
 }
 
-impl Responder for Option<String> {
+/// `None` is answered with 404 Not Found
+impl<T> Responder for Option<T>
+where
+    T: Responder,
+{
     fn respond_to(self) -> Response {
         match self {
             Some(resp) => resp.respond_to(),
@@ -100,14 +106,21 @@ impl Responder for Option<String> {
     }
 }
 
-impl Responder for Option<&'static str> {
+/// `Err` is answered with 500 Internal Server Error, the error is logged without leaking into the body
+impl<T, E> Responder for Result<T, E>
+where
+    T: Responder,
+    E: Display,
+{
     fn respond_to(self) -> Response {
         match self {
-            Some(resp) => resp.respond_to(),
-            None => "Not Found"
-                .to_string()
-                .with_status(StatusCode::NOT_FOUND)
-                .respond_to(),
+            Ok(resp) => resp.respond_to(),
+            Err(err) => {
+                eprintln!("handler failed: {}", err);
+                "Internal Server Error"
+                    .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .respond_to()
+            }
         }
     }
 }
@@ -131,35 +144,26 @@ mod test {
         // TODO: add testing for body once the Responder is refactored
     }
 
-    // #[test]
-    // fn test_option_responder() {
-    //     let some_result = Some("Hello World").respond_to();
-    //     if let Ok(response) = some_result {
-    //         assert_eq!(response.status(), StatusCode::OK);
-    //         // TODO: add testing for body once the Responder is refactored
-    //     }
-
-    //     let none_result = None::<String>.respond_to();
-    //     if let Ok(response) = none_result {
-    //         assert_eq!(response.status(), StatusCode::NOT_FOUND);
-    //         // TODO: add testing for body once the Responder is refactored
-    //     }
-    // }
-
-    // #[test]
-    // fn test_result_responder() {
-    //     let ok_result = Ok::<&str, &str>("Hello World").respond_to();
-    //     if let Ok(response) = ok_result {
-    //         assert_eq!(response.status(), StatusCode::OK);
-    //         // TODO: add testing for body once the Responder is refactored
-    //     }
-
-    //     let err_result = Err::<&str, &str>("Some error").respond_to();
-    //     if let Ok(response) = err_result {
-    //         assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
-    //         // TODO: add testing for body once the Responder is refactored
-    //     }
-    // }
+    #[test]
+    fn test_option_responder() {
+        let response = Some("Hello World").respond_to();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = None::<String>.respond_to();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = Some(StatusCode::CREATED).respond_to();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn test_result_responder() {
+        let response = Ok::<&str, &str>("Hello World").respond_to();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = Err::<&str, &str>("Some error").respond_to();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
 
     #[test]
     fn test_responder_with_custom_status() {
@@ -1,16 +1,32 @@
 use std::sync::Arc;
 
-use super::Handler;
+use super::{Guard, Handler};
+use crate::context::Context;
+use crate::middleware::Middleware;
 use crate::Method;
 
 pub struct Route {
     pub method: Method,
     pub handler: Arc<dyn Handler>,
+    /// Name for generating the url of the route
+    pub name: Option<String>,
+    /// Guards which must all pass for the route to serve the request
+    pub guards: Vec<Arc<dyn Guard>>,
+    /// Middlewares executed after the middlewares of the path, only for this route
+    pub middlewares: Vec<Arc<dyn Middleware>>,
+    /// Whether the registered path ends with '/', None if the path ends with a wildcard
+    pub(crate) trailing_slash: Option<bool>,
 }
 
 impl std::fmt::Debug for Route {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Route {{ method: {} }}", self.method)
+        write!(
+            f,
+            "Route {{ method: {}, guards: {}, middlewares: {} }}",
+            self.method,
+            self.guards.len(),
+            self.middlewares.len()
+        )
     }
 }
 
@@ -19,6 +35,10 @@ impl Clone for Route {
         Route {
             method: self.method.clone(),
             handler: self.handler.clone(),
+            name: self.name.clone(),
+            guards: self.guards.clone(),
+            middlewares: self.middlewares.clone(),
+            trailing_slash: self.trailing_slash,
         }
     }
 }
@@ -28,6 +48,27 @@ impl Route {
         Route {
             method,
             handler: Arc::new(handler),
+            name: None,
+            guards: Vec::new(),
+            middlewares: Vec::new(),
+            trailing_slash: Some(false),
         }
     }
+
+    /// Create the route which handles any request method, the method is shown as `*`
+    pub fn any(handler: impl Handler) -> Self {
+        Route {
+            method: Method::from_bytes(b"*").expect("'*' is a valid method token"),
+            handler: Arc::new(handler),
+            name: None,
+            guards: Vec::new(),
+            middlewares: Vec::new(),
+            trailing_slash: Some(false),
+        }
+    }
+
+    /// Check whether all of the guards pass for the request
+    pub fn check_guards(&self, ctx: &Context) -> bool {
+        self.guards.iter().all(|guard| guard.check(ctx))
+    }
 }
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use super::route_trie::RouteTrie;
+use super::{Guard, Router};
+use crate::error::RouteError;
+use crate::middleware::Middleware;
+use crate::Method;
+
+/// Builder returned by the route registration for configuring the registered route
+///
+/// # Example
+/// ```
+/// use obsidian::{context::Context, router::Router};
+///
+/// let mut router = Router::new();
+///
+/// router
+///     .get("/users/:id", |ctx: Context| async { ctx.build("user").ok() })
+///     .name("user");
+///
+/// assert_eq!(router.url_for("user", &[("id", "1")]).unwrap(), "/users/1");
+/// ```
+#[derive(Debug)]
+pub struct RouteBuilder<'a> {
+    routes: &'a mut RouteTrie,
+    path: String,
+    method: Method,
+}
+
+impl<'a> RouteBuilder<'a> {
+    pub(crate) fn new(routes: &'a mut RouteTrie, path: &str, method: Method) -> Self {
+        RouteBuilder {
+            routes,
+            path: path.to_string(),
+            method,
+        }
+    }
+
+    /// Name the route for generating its url through `url_for`.
+    /// Panic if the name is used by another route
+    pub fn name(self, name: &str) -> Self {
+        Router::expect_route(self.try_name(name))
+    }
+
+    /// Name the route for generating its url through `url_for`.
+    /// Error if the name is used by another route
+    pub fn try_name(self, name: &str) -> Result<Self, RouteError> {
+        self.routes.set_route_name(&self.path, &self.method, name)?;
+
+        Ok(self)
+    }
+
+    /// Add the guard which must pass for the route to serve the request.
+    /// Routes registered with the same path and method are checked in order
+    pub fn guard(self, guard: impl Guard) -> Self {
+        Router::expect_route(self.routes.add_route_guard(
+            &self.path,
+            &self.method,
+            Arc::new(guard),
+        ));
+
+        self
+    }
+
+    /// Apply the middleware only on this route, after the middlewares applied on the path
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian::{context::Context, middleware::logger::Logger, router::Router};
+    ///
+    /// let mut router = Router::new();
+    ///
+    /// router.get("/posts", |ctx: Context| async { ctx.build("posts").ok() });
+    /// router
+    ///     .post("/posts", |ctx: Context| async { ctx.build("created").ok() })
+    ///     .with(Logger::new());
+    /// ```
+    pub fn with(self, middleware: impl Middleware) -> Self {
+        Router::expect_route(self.routes.add_route_middleware(
+            &self.path,
+            &self.method,
+            Arc::new(middleware),
+        ));
+
+        self
+    }
+}
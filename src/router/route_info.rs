@@ -0,0 +1,20 @@
+use serde::{Serialize, Serializer};
+
+use crate::Method;
+
+/// Summary of a registered route, listed by `Router::routes`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RouteInfo {
+    /// Method of the route, `*` for the route handling any request method
+    #[serde(serialize_with = "serialize_method")]
+    pub method: Method,
+    /// Full path pattern of the route, including the prefixes of the merged routers
+    pub path: String,
+    /// Number of middlewares applied on the route, including the ones of its ancestors and the route itself
+    pub middleware_count: usize,
+    pub name: Option<String>,
+}
+
+fn serialize_method<S: Serializer>(method: &Method, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(method.as_str())
+}
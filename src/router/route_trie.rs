@@ -1,568 +1,1009 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 
 use hyper::Method;
+use percent_encoding::percent_decode_str;
+use smallvec::SmallVec;
 
+use crate::error::RouteError;
 use crate::middleware::Middleware;
+use crate::router::segment_pattern::{encode_segment, find_param, PatternError, SegmentPattern};
 use crate::router::Resource;
 use crate::router::Route;
+use crate::router::RouteInfo;
+use crate::router::{Guard, Handler};
 use crate::ObsidianError;
 
 #[derive(Clone, Default)]
 pub struct RouteValue {
     middlewares: Vec<Arc<dyn Middleware>>,
+    /// Middlewares of all ancestors followed by the node's own middlewares
+    resolved_middlewares: Vec<Arc<dyn Middleware>>,
     route: Resource,
+    /// Handler for the unmatched paths under the node
+    fallback: Option<Arc<dyn Handler>>,
 }
 
 impl fmt::Debug for RouteValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "")
+        f.debug_struct("RouteValue")
+            .field("middlewares", &self.resolved_middlewares.len())
+            .field("route", &self.route)
+            .field("fallback", &self.fallback.is_some())
+            .finish()
     }
 }
 
-impl RouteValue {
-    pub fn new(middlewares: Vec<Arc<dyn Middleware>>, route: Resource) -> Self {
-        RouteValue { middlewares, route }
-    }
-}
+/// Params captured while searching, the name is borrowed from the trie and the value from the path
+pub type RouteParams<'r, 'p> = SmallVec<[(&'r str, &'p str); 8]>;
 
-pub struct RouteValueResult {
-    route_value: RouteValue,
-    params: HashMap<String, String>,
+/// Search result borrowing the route value from the trie and the params from the searched path
+pub struct RouteValueResult<'r, 'p> {
+    route_value: &'r RouteValue,
+    params: RouteParams<'r, 'p>,
 }
 
-impl RouteValueResult {
-    pub fn new(route_value: RouteValue, params: HashMap<String, String>) -> Self {
+impl<'r, 'p> RouteValueResult<'r, 'p> {
+    pub fn new(route_value: &'r RouteValue, params: RouteParams<'r, 'p>) -> Self {
         RouteValueResult {
             route_value,
             params,
         }
     }
 
-    pub fn get_route(&self, method: &Method) -> Option<&Route> {
+    pub fn get_route(&self, method: &Method) -> Option<&'r Route> {
         self.route_value.route.get_route(method)
     }
 
-    pub fn get_middlewares(&self) -> &Vec<Arc<dyn Middleware>> {
-        &self.route_value.middlewares
+    /// Get the routes of the path, which outlive the searched path
+    pub fn get_resource(&self) -> &'r Resource {
+        &self.route_value.route
+    }
+
+    /// Check whether no route, including the any method route, is registered for the path
+    pub fn is_empty(&self) -> bool {
+        self.route_value.route.is_empty()
     }
 
-    pub fn get_params(&self) -> HashMap<String, String> {
-        self.params.clone()
+    pub fn get_methods(&self) -> Vec<&'r Method> {
+        self.route_value.route.get_methods()
+    }
+
+    pub fn get_allowed_methods(&self) -> Vec<Method> {
+        self.route_value.route.get_allowed_methods()
+    }
+
+    pub fn get_middlewares(&self) -> &'r [Arc<dyn Middleware>] {
+        &self.route_value.resolved_middlewares
+    }
+
+    /// Get the fallback of the node found by `search_fallback`
+    pub fn get_fallback(&self) -> Option<&'r Arc<dyn Handler>> {
+        self.route_value.fallback.as_ref()
+    }
+
+    pub fn get_params(&self) -> &[(&'r str, &'p str)] {
+        &self.params
+    }
+
+    /// Get the captured value of the param
+    pub fn get_param(&self, key: &str) -> Option<&'p str> {
+        self.params
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// Move the percent-decoded params into `params` so that the searched path is no longer borrowed,
+    /// replacing the existing params with the same name.
+    /// The raw values of the params changed by decoding are returned, None if no param is encoded.
+    /// Error if the decoded param is not valid utf-8
+    pub fn decode_params_into(
+        self,
+        params: &mut HashMap<String, String>,
+    ) -> Result<Option<HashMap<String, String>>, ObsidianError> {
+        let mut raw_params: Option<HashMap<String, String>> = None;
+
+        for (name, value) in self.params.iter() {
+            let decoded = percent_decode_str(value).decode_utf8().map_err(|_| {
+                ObsidianError::ParamError(format!("Param {} is not valid utf-8", name))
+            })?;
+
+            if let Cow::Owned(_) = decoded {
+                raw_params
+                    .get_or_insert_with(HashMap::new)
+                    .insert(name.to_string(), value.to_string());
+            }
+
+            params.insert(name.to_string(), decoded.into_owned());
+        }
+
+        Ok(raw_params)
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct RouteTrie {
     head: Node,
+    /// Match the static segments case-insensitively, including the ones of the merged sub tries
+    case_insensitive: bool,
 }
 
 impl RouteTrie {
     pub fn new() -> Self {
         RouteTrie {
             head: Node::new("/".to_string(), None),
+            case_insensitive: false,
         }
     }
 
+    /// Set whether the static segments are matched case-insensitively
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    pub fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
     /// Insert middleware into root node
     pub fn insert_default_middleware(&mut self, middleware: impl Middleware) {
-        match &mut self.head.value {
-            Some(val) => {
-                val.middlewares.push(Arc::new(middleware));
-            }
-            None => {
-                let mut val = RouteValue::default();
-                val.middlewares.push(Arc::new(middleware));
+        self.head
+            .value
+            .get_or_insert_with(RouteValue::default)
+            .middlewares
+            .push(Arc::new(middleware));
 
-                self.head.value = Some(val);
-            }
+        self.resolve_middlewares();
+    }
+
+    /// Get the middlewares of the root node
+    pub fn default_middlewares(&self) -> &[Arc<dyn Middleware>] {
+        self.head
+            .value
+            .as_ref()
+            .map(|val| val.middlewares.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Insert middlewares into root node before the existing ones
+    pub fn prepend_default_middlewares(&mut self, middlewares: &[Arc<dyn Middleware>]) {
+        if middlewares.is_empty() {
+            return;
         }
+
+        self.head
+            .value
+            .get_or_insert_with(RouteValue::default)
+            .middlewares
+            .splice(0..0, middlewares.iter().cloned());
+
+        self.resolve_middlewares();
+    }
+
+    /// Set the fallback of the root node, replacing the existing one
+    pub fn set_fallback(&mut self, handler: impl Handler) {
+        self.head
+            .value
+            .get_or_insert_with(RouteValue::default)
+            .fallback = Some(Arc::new(handler));
+
+        self.resolve_middlewares();
     }
 
     /// Insert route values into the trie
-    /// Panic if ambigous definition is detected
-    pub fn insert_route(&mut self, path: &str, route: Route) {
-        // Split path string and drop additional '/'
-        let mut split_key = path.split('/').filter(|key| !key.is_empty()).peekable();
-
-        split_key.clone().enumerate().for_each(|(pos, x)| {
-            if x.contains('*') {
-                if x.len() != 1 {
-                    panic!("ERROR: Consisting * in route name at: {}", path);
-                } else if pos != split_key.clone().count() - 1 {
-                    panic!("ERROR: * must be in the last at: {}", path);
-                }
-            }
-        });
+    /// Error if duplicated method or ambiguous definition is detected, the trie is left untouched
+    pub fn insert_route(&mut self, path: &str, mut route: Route) -> Result<(), RouteError> {
+        self.check_optional_conflict(path)?;
 
-        let mut curr_node = &mut self.head;
+        route.trailing_slash = trailing_slash(path);
 
-        // if the path is "/"
-        if split_key.peek().is_none() {
-            self.insert_default_route(route);
-            return;
+        let value = self.get_or_insert_value(path)?;
+
+        if let Err(rejected) = value.route.add_route(route.method.clone(), route) {
+            return Err(RouteError::DuplicateMethod {
+                method: rejected.method,
+                path: path.to_string(),
+            });
         }
 
-        while let Some(k) = split_key.next() {
-            match curr_node.process_insertion(k) {
-                Ok(next_node) => {
-                    if split_key.peek().is_none() {
-                        match &mut next_node.value {
-                            Some(val) => {
-                                if let Some(duplicated) =
-                                    val.route.add_route(route.method.clone(), route)
-                                {
-                                    panic!(
-                                        "Duplicated route method '{}' at '{}' detected",
-                                        duplicated.method, path
-                                    );
-                                }
-                            }
-                            None => {
-                                let mut next_node_val = RouteValue::default();
-                                if let Some(duplicated) =
-                                    next_node_val.route.add_route(route.method.clone(), route)
-                                {
-                                    panic!(
-                                        "Duplicated route method '{}' at '{}' detected",
-                                        duplicated.method, path
-                                    );
-                                }
-
-                                next_node.value = Some(next_node_val);
-                            }
-                        }
-                        break;
-                    }
-                    curr_node = next_node;
-                }
-                Err(err) => {
-                    panic!("Insert Route: {} at {}", err, path);
-                }
-            }
+        self.resolve_middlewares();
+
+        Ok(())
+    }
+
+    /// Insert the route which handles any request method into the trie
+    /// Error if duplicated any route or ambiguous definition is detected, the trie is left untouched
+    pub fn insert_any_route(&mut self, path: &str, mut route: Route) -> Result<(), RouteError> {
+        self.check_optional_conflict(path)?;
+
+        route.trailing_slash = trailing_slash(path);
+
+        let value = self.get_or_insert_value(path)?;
+
+        if let Some(duplicated) = value.route.add_any_route(route) {
+            let method = duplicated.method.clone();
+
+            // Restore the existing route
+            value.route.add_any_route(duplicated);
+
+            return Err(RouteError::DuplicateMethod {
+                method,
+                path: path.to_string(),
+            });
         }
+
+        self.resolve_middlewares();
+
+        Ok(())
     }
 
     /// Insert middleware into specific node
-    pub fn insert_middleware(&mut self, path: &str, middleware: impl Middleware) {
-        // Split key and drop additional '/'
-        let split_key = path.split('/');
-        let mut split_key = split_key.filter(|key| !key.is_empty()).peekable();
-
-        split_key.clone().enumerate().for_each(|(pos, key)| {
-            if key.contains('*') {
-                if key.len() != 1 {
-                    panic!("ERROR: Consisting * in route name at: {}", path);
-                } else if pos != split_key.clone().count() - 1 {
-                    panic!("ERROR: * must be in the last at: {}", path);
-                }
+    pub fn insert_middleware(
+        &mut self,
+        path: &str,
+        middleware: impl Middleware,
+    ) -> Result<(), RouteError> {
+        self.get_or_insert_value(path)?
+            .middlewares
+            .push(Arc::new(middleware));
+
+        self.resolve_middlewares();
+
+        Ok(())
+    }
+
+    /// Search node through the provided key
+    /// The result borrows the route value and the middlewares accumulated throughout the search path
+    pub fn search_route<'r, 'p>(&'r self, path: &'p str) -> Option<RouteValueResult<'r, 'p>> {
+        let mut params = RouteParams::new();
+
+        // Path is not registered if no node is found
+        let node = self
+            .head
+            .get_next_node(path, 0, self.case_insensitive, &mut params)?;
+
+        node.value
+            .as_ref()
+            .map(|val| RouteValueResult::new(val, params))
+    }
+
+    /// Search the deepest node with a fallback along the prefixes of the path matched by the trie.
+    /// The result has no params
+    pub fn search_fallback<'r, 'p>(&'r self, path: &'p str) -> Option<RouteValueResult<'r, 'p>> {
+        let (node, _) = self.head.find_fallback(path, 0, self.case_insensitive)?;
+
+        node.value
+            .as_ref()
+            .map(|val| RouteValueResult::new(val, RouteParams::new()))
+    }
+
+    /// Insert src trie into the des as a child trie
+    /// src will be under the node of des with the key path
+    ///
+    /// For example, /src/ -> /des/ with 'example' key path
+    /// src will be located at /des/example/src/
+    ///
+    /// Error if the des consists any routing data at the key path, the des is left untouched.
+    /// At the root path the src is merged into the des, error if both have routes on the same path
+    pub fn insert_sub_route(des: &mut Self, path: &str, src: Self) -> Result<(), RouteError> {
+        let is_root = split_path(path).next().is_none();
+
+        if let Some(node) = des.get_node(path)? {
+            if !is_root && (node.value.is_some() || !node.child_nodes.is_empty()) {
+                return Err(RouteError::SubRouterConflict {
+                    path: path.to_string(),
+                });
             }
-        });
+        }
 
-        let mut curr_node = &mut self.head;
+        let mut names = Vec::new();
+        src.head.collect_route_names(&mut names);
+
+        if let Some(name) = names
+            .into_iter()
+            .find(|name| des.find_named_route(name).is_some())
+        {
+            return Err(RouteError::DuplicateName {
+                name: name.to_string(),
+                path: path.to_string(),
+            });
+        }
 
-        while let Some(k) = split_key.next() {
-            match curr_node.process_insertion(k) {
-                Ok(next_node) => {
-                    if split_key.peek().is_none() {
-                        match &mut next_node.value {
-                            Some(val) => {
-                                val.middlewares.push(Arc::new(middleware));
-                            }
-                            None => {
-                                let mut next_node_val = RouteValue::default();
-                                next_node_val.middlewares.push(Arc::new(middleware));
-
-                                next_node.value = Some(next_node_val);
-                            }
-                        }
-                        break;
+        // The root is shared with the routes of the router, so the sub trie is merged node by node.
+        // The merge is done on a copy to leave the trie untouched on conflict
+        if is_root {
+            let mut merged = des.clone();
+            merged.merge_node(String::new(), src.head)?;
+            merged.resolve_middlewares();
+            *des = merged;
+
+            return Ok(());
+        }
+
+        let node = des.get_or_insert_node(path)?;
+        node.value = src.head.value;
+        node.child_nodes = src.head.child_nodes;
+
+        des.resolve_middlewares();
+
+        Ok(())
+    }
+
+    /// Merge the node of the sub trie and its descendants into the node at the path
+    /// Error if both nodes have routes or both have a fallback
+    fn merge_node(&mut self, path: String, src: Node) -> Result<(), RouteError> {
+        let node = self.get_or_insert_node(&path)?;
+
+        if let Some(src_value) = src.value {
+            match &mut node.value {
+                Some(value) => {
+                    let is_conflict = (!value.route.is_empty() && !src_value.route.is_empty())
+                        || (value.fallback.is_some() && src_value.fallback.is_some());
+
+                    if is_conflict {
+                        return Err(RouteError::SubRouterConflict {
+                            path: if path.is_empty() {
+                                "/".to_string()
+                            } else {
+                                path
+                            },
+                        });
+                    }
+
+                    value.middlewares.extend(src_value.middlewares);
+                    if value.route.is_empty() {
+                        value.route = src_value.route;
+                    }
+                    if value.fallback.is_none() {
+                        value.fallback = src_value.fallback;
                     }
-                    curr_node = next_node;
-                }
-                Err(err) => {
-                    panic!("Middleware: {} at {}", err, path);
                 }
+                None => node.value = Some(src_value),
             }
         }
+
+        for child in src.child_nodes {
+            self.merge_node(format!("{}/{}", path, child.key), child)?;
+        }
+
+        Ok(())
     }
 
-    /// Search node through the provided key
-    /// Middleware will be accumulated throughout the search path
-    pub fn search_route(&self, path: &str) -> Option<RouteValueResult> {
-        // Split key and drop additional '/'
-        let split_key = path.split('/');
-        let mut split_key = split_key
-            .filter(|key| !key.is_empty())
-            .collect::<Vec<&str>>();
+    /// Name the route registered with the method at the path
+    /// Error if the name is used by another route
+    pub fn set_route_name(
+        &mut self,
+        path: &str,
+        method: &Method,
+        name: &str,
+    ) -> Result<(), RouteError> {
+        if self.find_named_route(name).is_some() {
+            return Err(RouteError::DuplicateName {
+                name: name.to_string(),
+                path: path.to_string(),
+            });
+        }
 
-        let mut curr_node = &self.head;
-        let mut params = HashMap::default();
-        let mut middlewares = vec![];
+        if let Some(route) = self.get_route_mut(path, method)? {
+            route.name = Some(name.to_string());
+        }
 
-        match &curr_node.value {
-            Some(val) => {
-                middlewares.append(&mut val.middlewares.clone());
-            }
-            None => {}
+        Ok(())
+    }
+
+    /// Append the guard to the route registered last at the path with the method
+    pub fn add_route_guard(
+        &mut self,
+        path: &str,
+        method: &Method,
+        guard: Arc<dyn Guard>,
+    ) -> Result<(), RouteError> {
+        if let Some(route) = self.get_route_mut(path, method)? {
+            route.guards.push(guard);
         }
 
-        if !split_key.is_empty() {
-            match curr_node.get_next_node(&mut split_key, &mut params, &mut middlewares, false) {
-                Some(handler_node) => {
-                    curr_node = handler_node;
-                }
-                None => {
-                    // Path is not registered
-                    return None;
-                }
-            }
+        Ok(())
+    }
+
+    /// Append the middleware to the route registered last at the path with the method
+    pub fn add_route_middleware(
+        &mut self,
+        path: &str,
+        method: &Method,
+        middleware: Arc<dyn Middleware>,
+    ) -> Result<(), RouteError> {
+        if let Some(route) = self.get_route_mut(path, method)? {
+            route.middlewares.push(middleware);
         }
 
-        match &curr_node.value {
-            Some(val) => {
-                let route_val = RouteValue::new(middlewares, val.route.clone());
+        Ok(())
+    }
 
-                Some(RouteValueResult::new(route_val, params))
+    /// Get the route registered last at the path with the method
+    fn get_route_mut(
+        &mut self,
+        path: &str,
+        method: &Method,
+    ) -> Result<Option<&mut Route>, RouteError> {
+        Ok(self
+            .get_or_insert_node(path)?
+            .value
+            .as_mut()
+            .and_then(|val| val.route.get_route_mut(method)))
+    }
+
+    /// Build the url of the named route with the params, the params are percent-encoded
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, ObsidianError> {
+        let nodes = self.find_named_route(name).ok_or_else(|| {
+            ObsidianError::GeneralError(format!("Route named {} is not found", name))
+        })?;
+
+        let trailing_slash = nodes
+            .last()
+            .unwrap_or(&&self.head)
+            .find_route_named(name)
+            .and_then(|route| route.trailing_slash)
+            .unwrap_or(false);
+
+        let mut url = String::new();
+
+        for node in nodes {
+            if let Some(segment) = node.format_segment(params)? {
+                url.push('/');
+                url.push_str(&segment);
             }
-            None => None,
         }
+
+        if url.is_empty() || trailing_slash {
+            url.push('/');
+        }
+
+        Ok(url)
     }
 
-    /// Insert src trie into the des as a child trie
-    /// src will be under the node of des with the key path
-    ///
-    /// For example, /src/ -> /des/ with 'example' key path
-    /// src will be located at /des/example/src/
-    pub fn insert_sub_route(des: &mut Self, path: &str, src: Self) {
-        // Split key and drop additional '/'
-        let split_key = path.split('/');
-        let mut split_key = split_key.filter(|key| !key.is_empty()).peekable();
-
-        split_key.clone().enumerate().for_each(|(pos, x)| {
-            if x.contains('*') {
-                if x.len() != 1 {
-                    panic!("ERROR: Consisting * in route name at: {}", path);
-                } else if pos != split_key.clone().count() - 1 {
-                    panic!("ERROR: * must be in the last at: {}", path);
-                }
-            }
-        });
+    /// List the routes in the trie, ordered by the search priority of their paths
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        let mut routes = Vec::new();
 
-        let mut curr_node = &mut des.head;
+        self.head.collect_routes("", &mut routes);
 
-        if split_key.peek().is_none() {
-            des.head = src.head;
-            return;
+        routes
+    }
+
+    /// Get the nodes along the path of the named route
+    fn find_named_route(&self, name: &str) -> Option<Vec<&Node>> {
+        let mut nodes = Vec::new();
+
+        if self.head.find_named_route(name, &mut nodes) {
+            Some(nodes)
+        } else {
+            None
         }
+    }
 
-        while let Some(k) = split_key.next() {
-            match curr_node.process_insertion(k) {
-                Ok(next_node) => {
-                    if split_key.peek().is_none() {
-                        if next_node.value.is_some() || !next_node.child_nodes.is_empty() {
-                            panic!("There is conflict between main router and sub router at '{}'. Make sure main router does not consist any routing data in '{}'.", path, path);
-                        }
+    /// Get the route value of the path, create the nodes along the path if not exist
+    fn get_or_insert_value(&mut self, path: &str) -> Result<&mut RouteValue, RouteError> {
+        Ok(self
+            .get_or_insert_node(path)?
+            .value
+            .get_or_insert_with(RouteValue::default))
+    }
 
-                        next_node.value = src.head.value;
-                        next_node.child_nodes = src.head.child_nodes;
-                        break;
-                    }
-                    curr_node = next_node;
+    /// Get the node of the path, create the nodes along the path if not exist
+    /// The path is validated before any node is created
+    fn get_or_insert_node(&mut self, path: &str) -> Result<&mut Node, RouteError> {
+        self.get_node(path)?;
+
+        let mut curr_node = &mut self.head;
+
+        for key in split_path(path) {
+            curr_node = curr_node.process_insertion(key, path)?;
+        }
+
+        Ok(curr_node)
+    }
+
+    /// Get the existing node of the path without modifying the trie
+    /// Error if the path is invalid or ambiguous with the registered routes
+    fn get_node(&self, path: &str) -> Result<Option<&Node>, RouteError> {
+        let segment_count = split_path(path).count();
+
+        for (pos, key) in split_path(path).enumerate() {
+            let is_last = pos == segment_count - 1;
+
+            // Wildcard is either '*' or '*name', and only allowed as the last segment
+            let is_invalid_wildcard = match key.strip_prefix('*') {
+                Some(name) => name.contains('*') || !is_last,
+                None => key.contains('*') && !key.contains(':'),
+            };
+
+            if is_invalid_wildcard {
+                return Err(RouteError::InvalidWildcard {
+                    path: path.to_string(),
+                });
+            }
+
+            // Optional param is only allowed as the last segment
+            let pattern_error = match Node::parse_pattern(key) {
+                Ok(Some(pattern)) if pattern.is_optional() && !is_last => Some(PatternError::Param),
+                Ok(_) => None,
+                Err(err) => Some(err),
+            };
+
+            match pattern_error {
+                Some(PatternError::Param) => {
+                    return Err(RouteError::InvalidParam {
+                        path: path.to_string(),
+                        segment: key.to_string(),
+                    })
                 }
-                Err(err) => {
-                    panic!("SubRouter: {} at {}", err, path);
+                Some(PatternError::Constraint) => {
+                    return Err(RouteError::InvalidConstraint {
+                        path: path.to_string(),
+                        segment: key.to_string(),
+                    })
                 }
+                None => {}
             }
         }
-    }
 
-    fn insert_default_route(&mut self, route: Route) {
-        match &mut self.head.value {
-            Some(val) => {
-                if let Some(duplicated) = val.route.add_route(route.method.clone(), route) {
-                    panic!(
-                        "Duplicated route method '{}' at '/' detected",
-                        duplicated.method
-                    );
+        let mut curr_node = &self.head;
+
+        for key in split_path(path) {
+            let action = curr_node.get_insertion_action(key);
+
+            match action.name {
+                ActionName::NextNode => {
+                    curr_node = &curr_node.child_nodes[action.payload.node_index];
                 }
-            }
-            None => {
-                let mut val = RouteValue::default();
-                if let Some(duplicated) = val.route.add_route(route.method.clone(), route) {
-                    panic!(
-                        "Duplicated route method '{}' at '/' detected",
-                        duplicated.method
-                    );
+                ActionName::CreateNewNode => return Ok(None),
+                ActionName::Error => {
+                    return Err(curr_node.ambiguous_error(action.payload.node_index, key, path));
                 }
+            }
+        }
+
+        Ok(Some(curr_node))
+    }
 
-                self.head.value = Some(val);
+    /// Optional param matches the path without it as well,
+    /// so the routes must not be registered both with and without the optional param
+    fn check_optional_conflict(&self, path: &str) -> Result<(), RouteError> {
+        let keys = split_path(path).collect::<Vec<&str>>();
+
+        let conflict = match keys.split_last() {
+            Some((key, parent_keys))
+                if Node::parse_pattern(key)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|p| p.is_optional()) =>
+            {
+                self.find_node(parent_keys)
+                    .filter(|node| node.has_routes())
+                    .map(|node| (key.to_string(), node.key.clone()))
             }
+            _ => self.find_node(&keys).and_then(|node| {
+                node.child_nodes
+                    .iter()
+                    .find(|child| child.is_optional() && child.has_routes())
+                    .map(|child| (node.key.clone(), child.key.clone()))
+            }),
+        };
+
+        match conflict {
+            Some((segment, existing)) => Err(RouteError::AmbiguousParam {
+                path: path.to_string(),
+                segment,
+                existing,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Find the existing node with exactly the same keys
+    fn find_node(&self, keys: &[&str]) -> Option<&Node> {
+        let mut curr_node = &self.head;
+
+        for key in keys {
+            curr_node = curr_node.child_nodes.iter().find(|node| node.key == *key)?;
         }
+
+        Some(curr_node)
+    }
+
+    /// Resolve the middlewares applied on every node after the trie is modified,
+    /// so that searching does not need to accumulate them
+    fn resolve_middlewares(&mut self) {
+        self.head.resolve_middlewares(&[]);
     }
 }
 
+/// Split the route path into segments and drop additional '/'
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|key| !key.is_empty())
+}
+
+/// Whether the route path ends with '/', None if the path ends with a wildcard
+fn trailing_slash(path: &str) -> Option<bool> {
+    match split_path(path).last() {
+        Some(key) if key.starts_with('*') => None,
+        Some(_) => Some(path.ends_with('/')),
+        None => Some(false),
+    }
+}
+
+/// Get the next non-empty segment of the path starting from the cursor
+/// and the cursor position after the segment
+fn next_segment(path: &str, cursor: usize) -> Option<(&str, usize)> {
+    let rest = &path[cursor..];
+    let start = cursor + (rest.len() - rest.trim_start_matches('/').len());
+
+    if start == path.len() {
+        return None;
+    }
+
+    let end = path[start..]
+        .find('/')
+        .map(|len| start + len)
+        .unwrap_or_else(|| path.len());
+
+    Some((&path[start..end], end))
+}
+
+/// Node of the route trie, each node holds one segment of the route path
 #[derive(Clone, Debug)]
 struct Node {
     key: String,
     value: Option<RouteValue>,
     child_nodes: Vec<Node>,
+    /// Pattern of the node consisting params, None for static and wildcard node
+    pattern: Option<SegmentPattern>,
 }
 
 impl Node {
     fn new(key: String, value: Option<RouteValue>) -> Self {
+        let pattern = Self::parse_pattern(&key).unwrap_or_default();
+
         Node {
             key,
             value,
             child_nodes: Vec::default(),
+            pattern,
+        }
+    }
+
+    /// Parse the pattern of the key, None if the key is a static or wildcard segment
+    fn parse_pattern(key: &str) -> Result<Option<SegmentPattern>, PatternError> {
+        if key.starts_with('*') || !key.contains(':') {
+            return Ok(None);
+        }
+
+        SegmentPattern::parse(key).map(Some)
+    }
+
+    fn resolve_middlewares(&mut self, parent_middlewares: &[Arc<dyn Middleware>]) {
+        let mut resolved = parent_middlewares.to_vec();
+
+        if let Some(val) = &mut self.value {
+            resolved.extend(val.middlewares.iter().cloned());
+            val.resolved_middlewares = resolved.clone();
+        }
+
+        for child in self.child_nodes.iter_mut() {
+            child.resolve_middlewares(&resolved);
         }
     }
 
-    fn is_param(&self) -> bool {
-        self.key.chars().next().unwrap_or(' ') == ':'
+    fn is_wildcard(&self) -> bool {
+        self.key.starts_with('*')
+    }
+
+    fn is_optional(&self) -> bool {
+        self.pattern
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_optional())
+    }
+
+    /// Check whether any route is registered on the node, middlewares are not counted
+    fn has_routes(&self) -> bool {
+        self.value.as_ref().is_some_and(|val| !val.route.is_empty())
     }
 
     /// Process the side effects of node insertion
-    fn process_insertion(&mut self, key: &str) -> Result<&mut Self, ObsidianError> {
+    fn process_insertion(&mut self, key: &str, path: &str) -> Result<&mut Self, RouteError> {
         let action = self.get_insertion_action(key);
+        let index = action.payload.node_index;
 
         match action.name {
             ActionName::CreateNewNode => {
-                let new_node = Self::new(key.to_string(), None);
+                self.child_nodes
+                    .insert(index, Self::new(key.to_string(), None));
+            }
+            ActionName::NextNode => {}
+            ActionName::Error => return Err(self.ambiguous_error(index, key, path)),
+        }
 
-                match key {
-                    k if k == "*" => {
-                        self.child_nodes.push(new_node);
+        Ok(&mut self.child_nodes[index])
+    }
 
-                        if let Some(node) = self.child_nodes.last_mut() {
-                            return Ok(node);
-                        };
-                    }
-                    _ => {
-                        self.child_nodes.insert(0, new_node);
+    /// Error of the key conflicting with the child node at the index
+    fn ambiguous_error(&self, index: usize, key: &str, path: &str) -> RouteError {
+        RouteError::AmbiguousParam {
+            path: path.to_string(),
+            segment: key.to_string(),
+            existing: self.child_nodes[index].key.clone(),
+        }
+    }
 
-                        if let Some(node) = self.child_nodes.first_mut() {
-                            return Ok(node);
-                        };
-                    }
-                }
-            }
-            ActionName::NextNode => {
-                if let Some(node) = self.child_nodes.get_mut(action.payload.node_index) {
-                    return Ok(node);
-                };
+    /// Determine the action required to be performed for the new route segment
+    fn get_insertion_action(&self, key: &str) -> Action {
+        let new_node = Self::new(key.to_string(), None);
+        let priority = new_node.get_priority();
+
+        for (index, node) in self.child_nodes.iter().enumerate() {
+            if key == node.key {
+                return Action::new(ActionName::NextNode, ActionPayload::new(index));
             }
-            ActionName::SplitKey => {
-                if let Some(node) = self.child_nodes.get_mut(action.payload.node_index) {
-                    return node.process_insertion(&key[action.payload.match_count..]);
-                };
+
+            // Patterns at the same position must differ in more than the param names,
+            // and only one wildcard leaf is allowed in one children series
+            let is_ambiguous = match (&node.pattern, &new_node.pattern) {
+                (Some(pattern), Some(new_pattern)) => pattern.is_ambiguous_with(new_pattern),
+                _ => node.is_wildcard() && new_node.is_wildcard(),
+            };
+
+            if is_ambiguous {
+                return Action::new(ActionName::Error, ActionPayload::new(index));
             }
-            ActionName::SplitNode => {
-                if let Some(node) = self.child_nodes.get_mut(action.payload.node_index) {
-                    let count = action.payload.match_count;
-                    let child_key = node.key[count..].to_string();
-                    let new_key = key[count..].to_string();
-                    node.key = key[..count].to_string();
+        }
 
-                    let mut inter_node = Self::new(child_key, None);
+        // No child node matched the key, creates new node in front of the lower priority nodes
+        let index = self
+            .child_nodes
+            .iter()
+            .position(|node| node.get_priority() > priority)
+            .unwrap_or(self.child_nodes.len());
 
-                    // Move out the previous child and transfer to intermediate node
-                    inter_node.child_nodes = std::mem::take(&mut node.child_nodes);
-                    inter_node.value = std::mem::replace(&mut node.value, None);
+        Action::new(ActionName::CreateNewNode, ActionPayload::new(index))
+    }
 
-                    node.child_nodes.insert(0, inter_node);
+    /// Matching priority of the route segment, the lower the earlier.
+    /// Static segment goes first, then params embedded in static text, then constrained param,
+    /// then param, then wildcard. Nodes of the same priority are tried in the registration order.
+    fn get_priority(&self) -> u8 {
+        match &self.pattern {
+            Some(pattern) => 1 + pattern.priority(),
+            None if self.is_wildcard() => 4,
+            None => 0,
+        }
+    }
 
-                    // In the case of insert key length less than matched node key length
-                    if new_key.is_empty() {
-                        return Ok(node);
-                    }
+    fn has_route_named(&self, name: &str) -> bool {
+        self.find_route_named(name).is_some()
+    }
 
-                    let new_node = Self::new(new_key, None);
+    fn find_route_named(&self, name: &str) -> Option<&Route> {
+        self.value.as_ref().and_then(|val| {
+            val.route
+                .routes()
+                .find(|route| route.name.as_deref() == Some(name))
+        })
+    }
 
-                    node.child_nodes.insert(0, new_node);
-                    if let Some(result_node) = node.child_nodes.first_mut() {
-                        return Ok(result_node);
-                    }
-                };
-            }
-            ActionName::Error => {
-                if let Some(node) = self.child_nodes.get(action.payload.node_index) {
-                    return Err(ObsidianError::GeneralError(format!(
-                        "ERROR: Ambigous definition between {} and {}",
-                        key, node.key
-                    )));
-                }
+    /// Push the descendant nodes along the path until the node with the named route is found
+    fn find_named_route<'a>(&'a self, name: &str, nodes: &mut Vec<&'a Node>) -> bool {
+        if self.has_route_named(name) {
+            return true;
+        }
+
+        for child in self.child_nodes.iter() {
+            nodes.push(child);
+
+            if child.find_named_route(name, nodes) {
+                return true;
             }
+
+            nodes.pop();
         }
 
-        unreachable!();
+        false
     }
 
-    /// Determine the action required to be performed for the new route path
-    fn get_insertion_action(&self, key: &str) -> Action {
-        for (index, node) in self.child_nodes.iter().enumerate() {
-            let is_param = node.is_param() || key.chars().next().unwrap_or(' ') == ':';
-            if is_param {
-                // Only allow one param leaf in one children series
-                if key == node.key {
-                    return Action::new(ActionName::NextNode, ActionPayload::new(0, index));
-                } else {
-                    return Action::new(ActionName::Error, ActionPayload::new(0, index));
-                }
-            }
+    /// Collect the names of the routes in the node and its descendants
+    fn collect_route_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        if let Some(val) = &self.value {
+            names.extend(val.route.routes().filter_map(|route| route.name.as_deref()));
+        }
 
-            let mut temp_key_chars = key.chars();
-            let mut count = 0;
+        for child in self.child_nodes.iter() {
+            child.collect_route_names(names);
+        }
+    }
 
-            // match characters
-            for k in node.key.chars() {
-                let t_k = match temp_key_chars.next() {
-                    Some(key) => key,
-                    None => break,
-                };
+    /// Collect the routes of the node and its descendants, the path is the pattern of the node
+    /// which is empty for the head
+    fn collect_routes(&self, path: &str, routes: &mut Vec<RouteInfo>) {
+        if let Some(val) = &self.value {
+            let mut node_routes = val.route.routes().collect::<Vec<&Route>>();
+            // Any method route is listed after the method specific routes
+            node_routes.sort_by_key(|route| (route.method == "*", route.method.as_str()));
+
+            routes.extend(node_routes.into_iter().map(|route| RouteInfo {
+                method: route.method.clone(),
+                path: if path.is_empty() { "/" } else { path }.to_string(),
+                middleware_count: val.resolved_middlewares.len() + route.middlewares.len(),
+                name: route.name.clone(),
+            }));
+        }
 
-                if t_k == k {
-                    count += t_k.len_utf8();
-                } else {
-                    break;
-                }
-            }
+        for child in self.child_nodes.iter() {
+            child.collect_routes(&format!("{}/{}", path, child.key), routes);
+        }
+    }
 
-            match count {
-                x if x == key.len() && x == node.key.len() => {
-                    return Action::new(ActionName::NextNode, ActionPayload::new(x, index))
-                }
-                x if x == node.key.len() => {
-                    return Action::new(ActionName::SplitKey, ActionPayload::new(x, index))
-                }
-                x if x != 0 => {
-                    return Action::new(ActionName::SplitNode, ActionPayload::new(x, index))
-                }
-                _ => {}
+    /// Build the segment of the node with the params, only the params are percent-encoded
+    /// as the static keys are matched against the raw path as registered.
+    /// None if the segment is omitted, such as the optional param without value
+    fn format_segment(&self, params: &[(&str, &str)]) -> Result<Option<String>, ObsidianError> {
+        if let Some(pattern) = &self.pattern {
+            return pattern.format(params);
+        }
+
+        match self.key.strip_prefix('*') {
+            // Unnamed wildcard matches the empty rest of the path
+            Some("") => Ok(None),
+            Some(name) => {
+                let value = find_param(params, name)
+                    .ok_or_else(|| ObsidianError::ParamError(format!("Missing param {}", name)))?;
+
+                let segment = value
+                    .split('/')
+                    .filter(|key| !key.is_empty())
+                    .map(encode_segment)
+                    .collect::<Vec<String>>()
+                    .join("/");
+
+                Ok(Some(segment).filter(|segment| !segment.is_empty()))
             }
+            None => Ok(Some(self.key.clone())),
+        }
+    }
+
+    /// Capture the rest of the path into the params if the wildcard is named
+    fn capture_wildcard<'r, 'p>(
+        &'r self,
+        rest: &'p str,
+        params: &mut RouteParams<'r, 'p>,
+    ) -> &'r Self {
+        let name = &self.key[1..];
+
+        if !name.is_empty() {
+            params.push((name, rest));
         }
 
-        // No child node matched the key, creates new node
-        Action::new(ActionName::CreateNewNode, ActionPayload::new(0, 0))
+        self
     }
 
-    // Helper function to consume the whole key and get the next available node
-    fn get_next_node(
+    /// Walk the path from the cursor and get the deepest node with a fallback, with its depth below this node
+    fn find_fallback(
         &self,
-        key: &mut Vec<&str>,
-        params: &mut HashMap<String, String>,
-        middlewares: &mut Vec<std::sync::Arc<(dyn Middleware + 'static)>>,
-        is_break_parent: bool,
-    ) -> Option<&Self> {
-        let curr_key = key.remove(0);
+        path: &str,
+        cursor: usize,
+        case_insensitive: bool,
+    ) -> Option<(&Self, usize)> {
+        let mut deepest = self
+            .value
+            .as_ref()
+            .and_then(|val| val.fallback.as_ref())
+            .map(|_| (self, 0));
+
+        let (segment, next_cursor) = match next_segment(path, cursor) {
+            Some(next) => next,
+            None => return deepest,
+        };
 
         for node in self.child_nodes.iter() {
-            let mut break_key = false;
-
-            if !is_break_parent {
-                // Check param
-                if node.is_param() {
-                    if key.is_empty() {
-                        match &node.value {
-                            Some(curr_val) => {
-                                params.insert(node.key[1..].to_string(), curr_key.to_string());
-                                middlewares.append(&mut curr_val.middlewares.clone());
-                                return Some(node);
-                            }
-                            None => {
-                                continue;
-                            }
-                        }
-                    } else {
-                        match node.get_next_node(key, params, middlewares, break_key) {
-                            Some(final_val) => {
-                                params.insert(node.key[1..].to_string(), curr_key.to_string());
-
-                                match &node.value {
-                                    Some(curr_val) => {
-                                        middlewares.append(&mut curr_val.middlewares.clone());
-                                    }
-                                    None => {}
-                                }
-
-                                return Some(final_val);
-                            }
-                            None => {
-                                continue;
-                            }
-                        }
-                    }
+            let found = if node.is_wildcard() {
+                // Wildcard consumes the rest of the path
+                node.find_fallback(path, path.len(), case_insensitive)
+            } else if node.matches_segment(segment, case_insensitive) {
+                node.find_fallback(path, next_cursor, case_insensitive)
+            } else {
+                None
+            };
+
+            if let Some((fallback_node, depth)) = found {
+                if deepest.is_none_or(|(_, deepest_depth)| depth + 1 > deepest_depth) {
+                    deepest = Some((fallback_node, depth + 1));
                 }
+            }
+        }
 
-                // Check wildcard
-                if node.key == "*" {
-                    match &node.value {
-                        Some(curr_val) => {
-                            middlewares.append(&mut curr_val.middlewares.clone());
-                        }
-                        None => {}
-                    }
+        deepest
+    }
 
-                    return Some(node);
-                }
+    /// Check whether the segment matches the static key or the pattern of the node
+    fn matches_segment(&self, segment: &str, case_insensitive: bool) -> bool {
+        match &self.pattern {
+            Some(pattern) => pattern.match_segment(segment, &mut RouteParams::new()),
+            None => {
+                self.key == segment || (case_insensitive && self.key.eq_ignore_ascii_case(segment))
             }
+        }
+    }
 
-            let mut temp_key_chars = curr_key.chars();
-            let mut count = 0;
+    /// Walk the path from the cursor and get the node which consumes the whole path
+    /// Child nodes are tried in priority order, falling back to the next one when a branch is a dead end.
+    /// Params are pushed while walking and popped again when the branch is a dead end
+    fn get_next_node<'r, 'p>(
+        &'r self,
+        path: &'p str,
+        cursor: usize,
+        case_insensitive: bool,
+        params: &mut RouteParams<'r, 'p>,
+    ) -> Option<&'r Self> {
+        let (segment, next_cursor) = match next_segment(path, cursor) {
+            Some(next) => next,
+            None => {
+                if self.has_routes() {
+                    return Some(self);
+                }
 
-            // match characters
-            for k in node.key.chars() {
-                let t_k = match temp_key_chars.next() {
-                    Some(key) => key,
-                    None => break,
-                };
+                // Optional param and wildcard match the empty rest of the path
+                let node = self.child_nodes.iter().find(|node| {
+                    (node.is_optional() || node.is_wildcard()) && node.value.is_some()
+                });
 
-                if t_k == k {
-                    count += t_k.len_utf8();
-                } else {
-                    break;
-                }
+                return match node {
+                    Some(node) if node.is_wildcard() => {
+                        Some(node.capture_wildcard(&path[path.len()..], params))
+                    }
+                    Some(node) => Some(node),
+                    None if self.value.is_some() => Some(self),
+                    None => None,
+                };
             }
+        };
 
-            if count == node.key.len() && count != curr_key.len() {
-                // break key
-                break_key = true;
-                key.insert(0, &curr_key[count..]);
+        for node in self.child_nodes.iter() {
+            if node.is_wildcard() {
+                // The rest of the path starts from the current segment
+                let rest = &path[next_cursor - segment.len()..];
+
+                return Some(node.capture_wildcard(rest, params));
             }
 
-            if count != 0 && count == node.key.len() {
-                if key.is_empty() {
-                    match &node.value {
-                        Some(curr_val) => {
-                            middlewares.append(&mut curr_val.middlewares.clone());
-                            return Some(node);
-                        }
-                        None => {
-                            for child in node.child_nodes.iter() {
-                                if child.key == "*" {
-                                    if let Some(child_val) = &child.value {
-                                        middlewares.append(&mut child_val.middlewares.clone());
-                                        return Some(child);
-                                    }
-                                }
-                            }
-
-                            continue;
-                        }
+            match &node.pattern {
+                Some(pattern) => {
+                    let params_len = params.len();
+
+                    // Segment failing the pattern falls through to the next branch
+                    if !pattern.match_segment(segment, params) {
+                        continue;
                     }
-                } else if let Some(final_val) =
-                    node.get_next_node(key, params, middlewares, break_key)
-                {
-                    if let Some(curr_val) = &node.value {
-                        middlewares.append(&mut curr_val.middlewares.clone());
+
+                    if let Some(final_node) =
+                        node.get_next_node(path, next_cursor, case_insensitive, params)
+                    {
+                        return Some(final_node);
                     }
 
-                    return Some(final_val);
+                    params.truncate(params_len);
                 }
+                None if node.key == segment
+                    || (case_insensitive && node.key.eq_ignore_ascii_case(segment)) =>
+                {
+                    if let Some(final_node) =
+                        node.get_next_node(path, next_cursor, case_insensitive, params)
+                    {
+                        return Some(final_node);
+                    }
+                }
+                None => {}
             }
-
-            continue;
         }
 
         // Not found
@@ -574,16 +1015,12 @@ impl Node {
 enum ActionName {
     NextNode,
     CreateNewNode,
-    SplitNode,
-    SplitKey,
     Error,
 }
 
 /// Action Payload:
-/// characters matched for the node key and insert key
-/// node index in the node vector
+/// node index in the node vector, or the index to insert the new node
 struct ActionPayload {
-    match_count: usize,
     node_index: usize,
 }
 
@@ -600,11 +1037,8 @@ impl Action {
 }
 
 impl ActionPayload {
-    pub fn new(match_count: usize, node_index: usize) -> Self {
-        ActionPayload {
-            match_count,
-            node_index,
-        }
+    pub fn new(node_index: usize) -> Self {
+        ActionPayload { node_index }
     }
 }
 
@@ -625,7 +1059,9 @@ mod tests {
         let logger = Logger::new();
 
         route_trie.insert_default_middleware(logger);
-        route_trie.insert_route("/", Route::new(Method::GET, handler));
+        route_trie
+            .insert_route("/", Route::new(Method::GET, handler))
+            .unwrap();
 
         let result = route_trie.search_route("/");
 
@@ -650,9 +1086,15 @@ mod tests {
         let logger2 = Logger::new();
 
         route_trie.insert_default_middleware(logger);
-        route_trie.insert_route("/normal/test/", Route::new(Method::GET, handler));
-        route_trie.insert_route("/ノーマル/テスト/", Route::new(Method::GET, handler));
-        route_trie.insert_middleware("/ノーマル/テスト/", logger2);
+        route_trie
+            .insert_route("/normal/test/", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/ノーマル/テスト/", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_middleware("/ノーマル/テスト/", logger2)
+            .unwrap();
 
         let result = route_trie.search_route("/normal/test/");
 
@@ -691,7 +1133,9 @@ mod tests {
         let logger = Logger::new();
 
         route_trie.insert_default_middleware(logger);
-        route_trie.insert_route("/normal/test/", Route::new(Method::GET, handler));
+        route_trie
+            .insert_route("/normal/test/", Route::new(Method::GET, handler))
+            .unwrap();
 
         let result = route_trie.search_route("/fail/test/");
 
@@ -706,14 +1150,26 @@ mod tests {
         let logger3 = Logger::new();
 
         route_trie.insert_default_middleware(logger);
-        route_trie.insert_route("/normal/test/", Route::new(Method::GET, handler));
-        route_trie.insert_route("/noral/test/", Route::new(Method::GET, handler));
-        route_trie.insert_route("/ノーマル/テスト/", Route::new(Method::GET, handler));
-        route_trie.insert_route("/ノーマル/テーブル/", Route::new(Method::GET, handler));
-        route_trie.insert_middleware("/noral/test/", logger2);
-        route_trie.insert_middleware("/ノーマル/テーブル/", logger3);
-
-        let test_cases = vec![
+        route_trie
+            .insert_route("/normal/test/", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/noral/test/", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/ノーマル/テスト/", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/ノーマル/テーブル/", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_middleware("/noral/test/", logger2)
+            .unwrap();
+        route_trie
+            .insert_middleware("/ノーマル/テーブル/", logger3)
+            .unwrap();
+
+        let test_cases = [
             ("/normal/test/", 1),
             ("/noral/test/", 2),
             ("/ノーマル/テスト/", 1),
@@ -745,12 +1201,20 @@ mod tests {
         let logger2 = Logger::new();
         let logger3 = Logger::new();
 
-        route_trie.insert_route("/normal/test/*", Route::new(Method::GET, handler));
-        route_trie.insert_middleware("/normal/test/*", logger);
-        route_trie.insert_middleware("/normal/test/*", logger2);
-        route_trie.insert_middleware("/normal/test/*", logger3);
-
-        let test_cases = vec![
+        route_trie
+            .insert_route("/normal/test/*", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_middleware("/normal/test/*", logger)
+            .unwrap();
+        route_trie
+            .insert_middleware("/normal/test/*", logger2)
+            .unwrap();
+        route_trie
+            .insert_middleware("/normal/test/*", logger3)
+            .unwrap();
+
+        let test_cases = [
             "/normal/test/test",
             "/normal/test/123",
             "/normal/test/こんにちは",
@@ -775,21 +1239,731 @@ mod tests {
         }
     }
 
-    #[should_panic]
     #[test]
-    fn radix_trie_wildcard_param_conflict_test() {
+    fn radix_trie_named_wildcard_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/files/*path", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/assets/*", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let test_cases = [
+            ("/files/readme.md", "readme.md"),
+            ("/files/docs/guide/intro.md", "docs/guide/intro.md"),
+            ("//files//docs//intro.md", "docs//intro.md"),
+            ("/files/docs/", "docs/"),
+            ("/files", ""),
+        ];
+
+        for (path, captured) in test_cases.iter() {
+            let result = route_trie.search_route(path).unwrap();
+
+            assert_eq!(result.get_params().len(), 1);
+            assert_eq!(result.get_param("path"), Some(*captured));
+        }
+
+        // Plain wildcard does not capture
+        let result = route_trie.search_route("/assets/css/main.css").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert!(result.get_params().is_empty());
+    }
+
+    #[test]
+    fn radix_trie_wildcard_conflict_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/files/*path", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/files/*path", Route::new(Method::POST, handler))
+            .unwrap();
+
+        assert!(matches!(
+            route_trie.insert_route("/files/*", Route::new(Method::PUT, handler)),
+            Err(RouteError::AmbiguousParam { .. })
+        ));
+        assert!(matches!(
+            route_trie.insert_route("/files/*name", Route::new(Method::PUT, handler)),
+            Err(RouteError::AmbiguousParam { .. })
+        ));
+        assert!(matches!(
+            route_trie.insert_route("/files/*na*me", Route::new(Method::PUT, handler)),
+            Err(RouteError::InvalidWildcard { .. })
+        ));
+    }
+
+    #[test]
+    fn radix_trie_param_constraint_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/users/:slug", Route::new(Method::DELETE, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/users/:id<int>", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/users/:uid<uuid>", Route::new(Method::PUT, handler))
+            .unwrap();
+        route_trie
+            .insert_route(
+                r"/users/:code<[a-z]{2}\d{2}>",
+                Route::new(Method::POST, handler),
+            )
+            .unwrap();
+        route_trie
+            .insert_route("/posts/:id<int>", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let result = route_trie.search_route("/users/12").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_param("id"), Some("12"));
+
+        let result = route_trie
+            .search_route("/users/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .unwrap();
+
+        assert!(result.get_route(&Method::PUT).is_some());
+        assert_eq!(
+            result.get_param("uid"),
+            Some("67e55044-10b1-426f-9247-bb680e5fe0c8")
+        );
+
+        let result = route_trie.search_route("/users/ab12").unwrap();
+
+        assert!(result.get_route(&Method::POST).is_some());
+        assert_eq!(result.get_param("code"), Some("ab12"));
+
+        // Segment failing every constraint falls through to the unconstrained param
+        let result = route_trie.search_route("/users/abc").unwrap();
+
+        assert!(result.get_route(&Method::DELETE).is_some());
+        assert_eq!(result.get_params(), &[("slug", "abc")]);
+
+        // Out of the range of int
+        let result = route_trie
+            .search_route("/users/99999999999999999999")
+            .unwrap();
+
+        assert!(result.get_route(&Method::DELETE).is_some());
+
+        // No other branch to fall through
+        assert!(route_trie.search_route("/posts/abc").is_none());
+        assert!(route_trie.search_route("/posts/+42").is_none());
+        assert!(route_trie.search_route("/posts/12").is_some());
+    }
+
+    #[test]
+    fn radix_trie_param_constraint_conflict_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/users/:id<int>", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/users/:id<int>/posts", Route::new(Method::GET, handler))
+            .unwrap();
+
+        assert_eq!(
+            route_trie.insert_route("/users/:num<int>", Route::new(Method::GET, handler)),
+            Err(RouteError::AmbiguousParam {
+                path: "/users/:num<int>".to_string(),
+                segment: ":num<int>".to_string(),
+                existing: ":id<int>".to_string()
+            })
+        );
+        assert_eq!(
+            route_trie.insert_route("/users/:id<int", Route::new(Method::GET, handler)),
+            Err(RouteError::InvalidConstraint {
+                path: "/users/:id<int".to_string(),
+                segment: ":id<int".to_string()
+            })
+        );
+        assert!(matches!(
+            route_trie.insert_route("/users/:id<(>", Route::new(Method::GET, handler)),
+            Err(RouteError::InvalidConstraint { .. })
+        ));
+    }
+
+    #[test]
+    fn radix_trie_embedded_param_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/files/:name.:ext", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/files/:id", Route::new(Method::DELETE, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/v:version/items", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let result = route_trie.search_route("/files/report.pdf").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_params(), &[("name", "report"), ("ext", "pdf")]);
+
+        // Embedded params are tried before the whole segment param
+        let result = route_trie.search_route("/files/report").unwrap();
+
+        assert!(result.get_route(&Method::DELETE).is_some());
+        assert_eq!(result.get_params(), &[("id", "report")]);
+
+        let result = route_trie.search_route("/v2/items").unwrap();
+
+        assert_eq!(result.get_params(), &[("version", "2")]);
+        assert!(route_trie.search_route("/2/items").is_none());
+        assert!(route_trie.search_route("/v/items").is_none());
+    }
+
+    #[test]
+    fn radix_trie_optional_param_test() {
         let mut route_trie = RouteTrie::new();
+        let logger = Logger::new();
+
+        route_trie
+            .insert_route("/posts/:id/:slug?", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie.insert_middleware("/posts/:id", logger).unwrap();
+
+        let result = route_trie.search_route("/posts/1/hello-world").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_params(), &[("id", "1"), ("slug", "hello-world")]);
+        assert_eq!(result.get_middlewares().len(), 1);
 
-        route_trie.insert_route("/normal/test/*", Route::new(Method::GET, handler));
-        route_trie.insert_route("/normal/test/:param", Route::new(Method::GET, handler));
+        let result = route_trie.search_route("/posts/1/").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_params(), &[("id", "1")]);
+        assert_eq!(result.get_middlewares().len(), 1);
+
+        assert!(route_trie.search_route("/posts").is_none());
+        assert!(route_trie.search_route("/posts/1/hello/world").is_none());
+    }
+
+    #[test]
+    fn radix_trie_pattern_conflict_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/files/:name.:ext", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/posts/:id/:slug?", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/users/:id", Route::new(Method::GET, handler))
+            .unwrap();
+
+        assert!(matches!(
+            route_trie.insert_route("/files/:file.:type", Route::new(Method::GET, handler)),
+            Err(RouteError::AmbiguousParam { .. })
+        ));
+        assert!(matches!(
+            route_trie.insert_route("/posts/:id/:title", Route::new(Method::GET, handler)),
+            Err(RouteError::AmbiguousParam { .. })
+        ));
+        assert_eq!(
+            route_trie.insert_route("/posts/:id", Route::new(Method::POST, handler)),
+            Err(RouteError::AmbiguousParam {
+                path: "/posts/:id".to_string(),
+                segment: ":id".to_string(),
+                existing: ":slug?".to_string()
+            })
+        );
+        assert_eq!(
+            route_trie.insert_route("/users/:id/:tab?", Route::new(Method::GET, handler)),
+            Err(RouteError::AmbiguousParam {
+                path: "/users/:id/:tab?".to_string(),
+                segment: ":tab?".to_string(),
+                existing: ":id".to_string()
+            })
+        );
+        assert_eq!(
+            route_trie.insert_route("/posts/:slug?/edit", Route::new(Method::GET, handler)),
+            Err(RouteError::InvalidParam {
+                path: "/posts/:slug?/edit".to_string(),
+                segment: ":slug?".to_string()
+            })
+        );
+        assert!(matches!(
+            route_trie.insert_route("/files/:name:ext", Route::new(Method::GET, handler)),
+            Err(RouteError::InvalidParam { .. })
+        ));
+
+        // Different static text between the params is not ambiguous
+        route_trie
+            .insert_route("/files/:from-:to", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let result = route_trie.search_route("/files/a-b").unwrap();
+
+        assert_eq!(result.get_params(), &[("from", "a"), ("to", "b")]);
+    }
+
+    #[test]
+    fn radix_trie_wildcard_param_priority_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/normal/test/*", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/normal/test/:param", Route::new(Method::POST, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/normal/test/static", Route::new(Method::PUT, handler))
+            .unwrap();
+
+        let result = route_trie.search_route("/normal/test/static").unwrap();
+        assert!(result.get_route(&Method::PUT).is_some());
+        assert!(result.get_params().is_empty());
+
+        let result = route_trie.search_route("/normal/test/value").unwrap();
+        assert!(result.get_route(&Method::POST).is_some());
+        assert_eq!(result.get_param("param"), Some("value"));
+
+        let result = route_trie
+            .search_route("/normal/test/value/nested")
+            .unwrap();
+        assert!(result.get_route(&Method::GET).is_some());
+        assert!(result.get_params().is_empty());
+    }
+
+    #[test]
+    fn radix_trie_param_wildcard_priority_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/normal/test/:param", Route::new(Method::POST, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/normal/test/*", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let result = route_trie.search_route("/normal/test/value").unwrap();
+        assert!(result.get_route(&Method::POST).is_some());
+
+        let result = route_trie
+            .search_route("/normal/test/value/nested")
+            .unwrap();
+        assert!(result.get_route(&Method::GET).is_some());
+    }
+
+    #[test]
+    fn radix_trie_static_backtrack_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/users/me/profile", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/users/:id/settings", Route::new(Method::POST, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/users/*", Route::new(Method::PUT, handler))
+            .unwrap();
+
+        let result = route_trie.search_route("/users/me/profile").unwrap();
+        assert!(result.get_route(&Method::GET).is_some());
+
+        // Static branch of 'me' is a dead end, falls back to the param
+        let result = route_trie.search_route("/users/me/settings").unwrap();
+        assert!(result.get_route(&Method::POST).is_some());
+        assert_eq!(result.get_param("id"), Some("me"));
+
+        // Both static and param branches are dead ends, falls back to the wildcard
+        let result = route_trie.search_route("/users/me/unknown").unwrap();
+        assert!(result.get_route(&Method::PUT).is_some());
+        assert!(result.get_params().is_empty());
     }
 
     #[should_panic]
     #[test]
-    fn radix_trie_param_wildcard_conflict_test() {
+    fn radix_trie_param_conflict_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/normal/test/:param", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/normal/test/:other", Route::new(Method::GET, handler))
+            .unwrap();
+    }
+
+    #[test]
+    fn radix_trie_param_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route(
+                "/admin/:org/users/:id/roles/:role",
+                Route::new(Method::GET, handler),
+            )
+            .unwrap();
+        route_trie
+            .insert_route("/admin/:org", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let result = route_trie
+            .search_route("/admin/obsidian/users/12/roles/owner")
+            .unwrap();
+
+        assert_eq!(result.get_param("org"), Some("obsidian"));
+        assert_eq!(result.get_param("id"), Some("12"));
+        assert_eq!(result.get_param("role"), Some("owner"));
+        assert_eq!(result.get_params().len(), 3);
+
+        let result = route_trie.search_route("//admin///obsidian/").unwrap();
+
+        assert_eq!(result.get_param("org"), Some("obsidian"));
+        assert_eq!(result.get_params().len(), 1);
+
+        // Params of the dead end branch are not kept
+        assert!(route_trie
+            .search_route("/admin/obsidian/users/12/roles")
+            .is_none());
+    }
+
+    #[test]
+    fn radix_trie_segment_boundary_test() {
+        let mut route_trie = RouteTrie::new();
+        let logger = Logger::new();
+
+        route_trie
+            .insert_route("/ab/c", Route::new(Method::GET, handler))
+            .unwrap();
+        route_trie
+            .insert_route("/abc", Route::new(Method::POST, handler))
+            .unwrap();
+        route_trie.insert_middleware("/abc", logger).unwrap();
+
+        let result = route_trie.search_route("/ab/c").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert!(result.get_route(&Method::POST).is_none());
+        assert_eq!(result.get_middlewares().len(), 0);
+
+        let result = route_trie.search_route("/abc").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_none());
+        assert!(result.get_route(&Method::POST).is_some());
+        assert_eq!(result.get_middlewares().len(), 1);
+
+        assert!(route_trie.search_route("/ab").is_none());
+        assert!(route_trie.search_route("/abcd").is_none());
+    }
+
+    #[test]
+    fn radix_trie_fallback_test() {
         let mut route_trie = RouteTrie::new();
+        let mut api_trie = RouteTrie::new();
+        let mut users_trie = RouteTrie::new();
+
+        route_trie.set_fallback(handler);
+        route_trie
+            .insert_route("/about", Route::new(Method::GET, handler))
+            .unwrap();
+
+        api_trie.set_fallback(handler);
+        api_trie.insert_default_middleware(Logger::new());
+
+        users_trie.set_fallback(handler);
+        users_trie
+            .insert_route("/list", Route::new(Method::GET, handler))
+            .unwrap();
+
+        RouteTrie::insert_sub_route(&mut api_trie, "/users/:id<int>", users_trie).unwrap();
+        RouteTrie::insert_sub_route(&mut route_trie, "/api", api_trie).unwrap();
+
+        let depth_of = |path: &str| {
+            let (node, depth) = route_trie.head.find_fallback(path, 0, false).unwrap();
+            (node.key.clone(), depth)
+        };
+
+        assert_eq!(depth_of("/missing"), ("/".to_string(), 0));
+        assert_eq!(depth_of("/about/missing"), ("/".to_string(), 0));
+        assert_eq!(depth_of("/api"), ("api".to_string(), 1));
+        assert_eq!(depth_of("/api/missing"), ("api".to_string(), 1));
+        assert_eq!(depth_of("/api/users/abc/list"), ("api".to_string(), 1));
+        assert_eq!(
+            depth_of("/api/users/1/missing"),
+            (":id<int>".to_string(), 3)
+        );
+
+        let result = route_trie.search_fallback("/api/missing").unwrap();
+
+        assert!(result.get_fallback().is_some());
+        assert_eq!(result.get_middlewares().len(), 1);
+        assert!(result.get_params().is_empty());
+
+        assert!(RouteTrie::new().search_fallback("/missing").is_none());
+    }
+
+    #[test]
+    fn radix_trie_case_insensitive_test() {
+        let mut route_trie = RouteTrie::new();
+        let mut sub_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/Users/:id", Route::new(Method::GET, handler))
+            .unwrap();
+        sub_trie
+            .insert_route("/Profile", Route::new(Method::GET, handler))
+            .unwrap();
+        RouteTrie::insert_sub_route(&mut route_trie, "/admin", sub_trie).unwrap();
+
+        assert!(route_trie.search_route("/users/Jun").is_none());
+        assert!(route_trie.search_route("/ADMIN/profile").is_none());
+
+        route_trie.set_case_insensitive(true);
+
+        let result = route_trie.search_route("/users/Jun").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_param("id"), Some("Jun"));
+        assert!(route_trie.search_route("/USERS/Jun").is_some());
+        assert!(route_trie.search_route("/ADMIN/profile").is_some());
+        assert!(route_trie.search_route("/admin/profiles").is_none());
+    }
+
+    #[test]
+    fn radix_trie_decode_params_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/files/:name/:kind/*path", Route::new(Method::GET, handler))
+            .unwrap();
+
+        let mut params = HashMap::new();
+        params.insert("kind".to_string(), "host".to_string());
+
+        let raw_params = route_trie
+            .search_route("/files/my%20doc/text/a%2Fb/%E6%97%A5")
+            .unwrap()
+            .decode_params_into(&mut params)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(params.get("name"), Some(&"my doc".to_string()));
+        assert_eq!(params.get("kind"), Some(&"text".to_string()));
+        assert_eq!(params.get("path"), Some(&"a/b/日".to_string()));
+
+        // Only the params changed by decoding keep the raw values
+        assert_eq!(raw_params.get("name"), Some(&"my%20doc".to_string()));
+        assert_eq!(raw_params.get("kind"), None);
+        assert_eq!(raw_params.get("path"), Some(&"a%2Fb/%E6%97%A5".to_string()));
+
+        let mut params = HashMap::new();
+        let raw_params = route_trie
+            .search_route("/files/doc/text/a")
+            .unwrap()
+            .decode_params_into(&mut params)
+            .unwrap();
+
+        assert_eq!(params.len(), 3);
+        assert_eq!(raw_params, None);
+
+        let result = route_trie.search_route("/files/%FF%FE/text/a").unwrap();
+
+        assert!(matches!(
+            result.decode_params_into(&mut HashMap::new()),
+            Err(ObsidianError::ParamError(_))
+        ));
+    }
+
+    #[test]
+    fn radix_trie_duplicate_method_error_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/users", Route::new(Method::GET, handler))
+            .unwrap();
+
+        assert_eq!(
+            route_trie.insert_route("/users", Route::new(Method::GET, handler)),
+            Err(RouteError::DuplicateMethod {
+                method: Method::GET,
+                path: "/users".to_string()
+            })
+        );
+
+        route_trie
+            .insert_any_route("/users", Route::any(handler))
+            .unwrap();
+
+        assert!(matches!(
+            route_trie.insert_any_route("/users", Route::any(handler)),
+            Err(RouteError::DuplicateMethod { .. })
+        ));
+
+        // The existing routes are kept
+        let result = route_trie.search_route("/users").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert!(result.get_route(&Method::POST).is_some());
+    }
 
-        route_trie.insert_route("/normal/test/:param", Route::new(Method::GET, handler));
-        route_trie.insert_route("/normal/test/*", Route::new(Method::GET, handler));
+    #[test]
+    fn radix_trie_ambiguous_param_error_test() {
+        let mut route_trie = RouteTrie::new();
+
+        route_trie
+            .insert_route("/users/:id", Route::new(Method::GET, handler))
+            .unwrap();
+
+        assert_eq!(
+            route_trie.insert_route("/users/:name/edit", Route::new(Method::GET, handler)),
+            Err(RouteError::AmbiguousParam {
+                path: "/users/:name/edit".to_string(),
+                segment: ":name".to_string(),
+                existing: ":id".to_string()
+            })
+        );
+
+        assert!(matches!(
+            route_trie.insert_middleware("/users/:name", Logger::new()),
+            Err(RouteError::AmbiguousParam { .. })
+        ));
+
+        assert!(route_trie.search_route("/users/1/edit").is_none());
+        assert_eq!(
+            route_trie
+                .search_route("/users/1")
+                .unwrap()
+                .get_middlewares()
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn radix_trie_invalid_wildcard_error_test() {
+        let mut route_trie = RouteTrie::new();
+
+        assert_eq!(
+            route_trie.insert_route("/files/*/list", Route::new(Method::GET, handler)),
+            Err(RouteError::InvalidWildcard {
+                path: "/files/*/list".to_string()
+            })
+        );
+
+        assert!(matches!(
+            route_trie.insert_route("/files/a*", Route::new(Method::GET, handler)),
+            Err(RouteError::InvalidWildcard { .. })
+        ));
+
+        // No node is left behind by the failed insertions
+        assert!(route_trie.head.child_nodes.is_empty());
+    }
+
+    #[test]
+    fn radix_trie_sub_route_conflict_error_test() {
+        let mut main_trie = RouteTrie::new();
+        let mut sub_trie = RouteTrie::new();
+
+        main_trie
+            .insert_route("/admin/users", Route::new(Method::GET, handler))
+            .unwrap();
+        sub_trie
+            .insert_route("/list", Route::new(Method::GET, handler))
+            .unwrap();
+
+        assert_eq!(
+            RouteTrie::insert_sub_route(&mut main_trie, "/admin", sub_trie.clone()),
+            Err(RouteError::SubRouterConflict {
+                path: "/admin".to_string()
+            })
+        );
+
+        assert!(main_trie.search_route("/admin/list").is_none());
+
+        RouteTrie::insert_sub_route(&mut main_trie, "/manage", sub_trie).unwrap();
+
+        assert!(main_trie.search_route("/manage/list").is_some());
+    }
+
+    #[test]
+    fn radix_trie_root_sub_route_test() {
+        let mut main_trie = RouteTrie::new();
+        let mut sub_trie = RouteTrie::new();
+
+        main_trie.insert_default_middleware(Logger::new());
+        main_trie
+            .insert_route("/", Route::new(Method::GET, handler))
+            .unwrap();
+        main_trie
+            .insert_route("/users", Route::new(Method::GET, handler))
+            .unwrap();
+        sub_trie.insert_default_middleware(Logger::new());
+        sub_trie
+            .insert_route("/users/:id", Route::new(Method::GET, handler))
+            .unwrap();
+        sub_trie
+            .insert_route("/posts", Route::new(Method::GET, handler))
+            .unwrap();
+
+        RouteTrie::insert_sub_route(&mut main_trie, "/", sub_trie).unwrap();
+
+        let result = main_trie.search_route("/").unwrap();
+
+        assert!(result.get_route(&Method::GET).is_some());
+        assert_eq!(result.get_middlewares().len(), 2);
+        assert!(main_trie
+            .search_route("/users")
+            .unwrap()
+            .get_route(&Method::GET)
+            .is_some());
+        assert!(main_trie
+            .search_route("/users/1")
+            .unwrap()
+            .get_route(&Method::GET)
+            .is_some());
+        assert_eq!(
+            main_trie
+                .search_route("/posts")
+                .unwrap()
+                .get_middlewares()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn radix_trie_root_sub_route_conflict_error_test() {
+        let mut main_trie = RouteTrie::new();
+        let mut sub_trie = RouteTrie::new();
+
+        main_trie
+            .insert_route("/users", Route::new(Method::GET, handler))
+            .unwrap();
+        sub_trie
+            .insert_route("/posts", Route::new(Method::GET, handler))
+            .unwrap();
+        sub_trie
+            .insert_route("/users", Route::new(Method::POST, handler))
+            .unwrap();
+
+        assert_eq!(
+            RouteTrie::insert_sub_route(&mut main_trie, "", sub_trie),
+            Err(RouteError::SubRouterConflict {
+                path: "/users".to_string()
+            })
+        );
+
+        assert!(main_trie.search_route("/posts").is_none());
+        assert!(main_trie
+            .search_route("/users")
+            .unwrap()
+            .get_route(&Method::POST)
+            .is_none());
     }
 }
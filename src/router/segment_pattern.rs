@@ -0,0 +1,460 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use super::param_constraint::ParamConstraint;
+use super::route_trie::RouteParams;
+use crate::ObsidianError;
+
+/// Characters to be percent-encoded in a path segment
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Percent-encode the value to be placed in a path segment
+pub fn encode_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// Get the value of the param from the provided params
+pub fn find_param<'a>(params: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| *value)
+}
+
+/// Part of a route segment pattern
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Static(String),
+    Param {
+        name: String,
+        constraint: Option<ParamConstraint>,
+    },
+}
+
+/// Errors occurs while parsing the segment pattern
+#[derive(Debug, PartialEq)]
+pub enum PatternError {
+    /// Empty param name, params without static text in between, or misplaced `?`
+    Param,
+    /// Unclosed constraint or invalid regex
+    Constraint,
+}
+
+/// Pattern of a route segment consisting params
+///
+/// A param is `:name` or `:name<constraint>`, and the name ends at the first character which is
+/// not alphanumeric or '_'. Params can be embedded with static prefix and suffix, such as
+/// `:name.:ext` or `v:version`, as long as every two params are separated by static text.
+/// A param matches as few characters as possible, except the last one which takes the rest.
+///
+/// A whole segment param followed by `?`, such as `:slug?`, is optional.
+#[derive(Clone, Debug)]
+pub struct SegmentPattern {
+    parts: Vec<Part>,
+    optional: bool,
+}
+
+impl SegmentPattern {
+    pub fn parse(key: &str) -> Result<Self, PatternError> {
+        let mut parts = Vec::new();
+        let mut optional = false;
+        let mut rest = key;
+
+        while !rest.is_empty() {
+            // '?' is only allowed in the last
+            if optional {
+                return Err(PatternError::Param);
+            }
+
+            match rest.strip_prefix(':') {
+                Some(param) => {
+                    if let Some(Part::Param { .. }) = parts.last() {
+                        return Err(PatternError::Param);
+                    }
+
+                    let name_len = param
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(param.len());
+
+                    if name_len == 0 {
+                        return Err(PatternError::Param);
+                    }
+
+                    let name = param[..name_len].to_string();
+                    rest = &param[name_len..];
+
+                    let constraint = match rest.strip_prefix('<') {
+                        Some(constraint) => {
+                            let end = constraint_end(constraint).ok_or(PatternError::Constraint)?;
+                            rest = &constraint[end + 1..];
+
+                            Some(
+                                ParamConstraint::new(&constraint[..end])
+                                    .map_err(|_| PatternError::Constraint)?,
+                            )
+                        }
+                        None => None,
+                    };
+
+                    if let Some(after) = rest.strip_prefix('?') {
+                        optional = true;
+                        rest = after;
+                    }
+
+                    parts.push(Part::Param { name, constraint });
+                }
+                None => {
+                    let end = rest.find(':').unwrap_or(rest.len());
+                    let text = &rest[..end];
+
+                    if text.contains(['<', '>', '?']) {
+                        return Err(PatternError::Param);
+                    }
+
+                    parts.push(Part::Static(text.to_string()));
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        let pattern = SegmentPattern { parts, optional };
+
+        // Only whole segment param can be optional
+        if pattern.optional && !pattern.is_whole_param() {
+            return Err(PatternError::Param);
+        }
+
+        Ok(pattern)
+    }
+
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Matching priority among the patterns, the lower the earlier.
+    /// Params embedded with static text go first, then constrained param, then param.
+    pub fn priority(&self) -> u8 {
+        match self.parts.as_slice() {
+            [Part::Param {
+                constraint: Some(_),
+                ..
+            }] => 1,
+            [Part::Param {
+                constraint: None, ..
+            }] => 2,
+            _ => 0,
+        }
+    }
+
+    /// Two patterns are ambiguous if they only differ in the param names
+    pub fn is_ambiguous_with(&self, other: &Self) -> bool {
+        self.parts.len() == other.parts.len()
+            && self
+                .parts
+                .iter()
+                .zip(other.parts.iter())
+                .all(|parts| match parts {
+                    (Part::Static(a), Part::Static(b)) => a == b,
+                    (
+                        Part::Param {
+                            constraint: a_constraint,
+                            ..
+                        },
+                        Part::Param {
+                            constraint: b_constraint,
+                            ..
+                        },
+                    ) => a_constraint == b_constraint,
+                    _ => false,
+                })
+    }
+
+    /// Match the whole segment and push the captured params
+    /// The params are left untouched if the segment does not match
+    pub fn match_segment<'r, 'p>(
+        &'r self,
+        segment: &'p str,
+        params: &mut RouteParams<'r, 'p>,
+    ) -> bool {
+        Self::match_parts(&self.parts, segment, params)
+    }
+
+    /// Build the segment with the percent-encoded params
+    /// None if the param of the optional segment is not provided
+    pub fn format(&self, params: &[(&str, &str)]) -> Result<Option<String>, ObsidianError> {
+        let mut segment = String::new();
+
+        for part in self.parts.iter() {
+            match part {
+                Part::Static(text) => segment.push_str(text),
+                Part::Param { name, constraint } => {
+                    let value = match find_param(params, name) {
+                        Some(value) => value,
+                        None if self.optional => return Ok(None),
+                        None => {
+                            return Err(ObsidianError::ParamError(format!(
+                                "Missing param {}",
+                                name
+                            )))
+                        }
+                    };
+
+                    let is_match = !value.is_empty()
+                        && constraint
+                            .as_ref()
+                            .is_none_or(|constraint| constraint.is_match(value));
+
+                    if !is_match {
+                        return Err(ObsidianError::ParamError(format!(
+                            "Param {} does not match the route pattern",
+                            name
+                        )));
+                    }
+
+                    segment.push_str(&encode_segment(value));
+                }
+            }
+        }
+
+        Ok(Some(segment))
+    }
+
+    fn is_whole_param(&self) -> bool {
+        matches!(self.parts.as_slice(), [Part::Param { .. }])
+    }
+
+    fn match_parts<'r, 'p>(
+        parts: &'r [Part],
+        segment: &'p str,
+        params: &mut RouteParams<'r, 'p>,
+    ) -> bool {
+        let (part, rest_parts) = match parts.split_first() {
+            Some(split) => split,
+            None => return segment.is_empty(),
+        };
+
+        match part {
+            Part::Static(text) => match segment.strip_prefix(text.as_str()) {
+                Some(rest) => Self::match_parts(rest_parts, rest, params),
+                None => false,
+            },
+            Part::Param { name, constraint } => {
+                let is_match = |value: &str| {
+                    !value.is_empty()
+                        && constraint
+                            .as_ref()
+                            .is_none_or(|constraint| constraint.is_match(value))
+                };
+
+                // The last param takes the rest of the segment
+                if rest_parts.is_empty() {
+                    if is_match(segment) {
+                        params.push((name, segment));
+                        return true;
+                    }
+
+                    return false;
+                }
+
+                // Try the shortest value first, falling back to the longer ones
+                for (end, _) in segment.char_indices().skip(1) {
+                    let value = &segment[..end];
+
+                    if !is_match(value) {
+                        continue;
+                    }
+
+                    params.push((name, value));
+
+                    if Self::match_parts(rest_parts, &segment[end..], params) {
+                        return true;
+                    }
+
+                    params.pop();
+                }
+
+                false
+            }
+        }
+    }
+}
+
+/// Position of the '>' closing the constraint, nested '<' '>' pairs and escaped characters are skipped
+fn constraint_end(constraint: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = constraint.char_indices();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '<' => depth += 1,
+            '>' if depth == 0 => return Some(pos),
+            '>' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures<'r, 'p>(
+        pattern: &'r SegmentPattern,
+        segment: &'p str,
+    ) -> Option<Vec<(&'r str, &'p str)>> {
+        let mut params = RouteParams::new();
+
+        if pattern.match_segment(segment, &mut params) {
+            Some(params.to_vec())
+        } else {
+            assert!(params.is_empty());
+            None
+        }
+    }
+
+    #[test]
+    fn segment_pattern_parse_test() {
+        assert!(SegmentPattern::parse(":id").is_ok());
+        assert!(SegmentPattern::parse(r":id<\d+>").is_ok());
+        assert!(SegmentPattern::parse(":name.:ext").is_ok());
+        assert!(SegmentPattern::parse("v:version").is_ok());
+        assert!(SegmentPattern::parse(":slug?").is_ok());
+        assert!(SegmentPattern::parse(":id<int>?").is_ok());
+        assert!(SegmentPattern::parse("(?P<year>\\d{4})").is_err());
+
+        assert_eq!(
+            SegmentPattern::parse(":a:b").unwrap_err(),
+            PatternError::Param
+        );
+        assert_eq!(SegmentPattern::parse(":").unwrap_err(), PatternError::Param);
+        assert_eq!(
+            SegmentPattern::parse(":.json").unwrap_err(),
+            PatternError::Param
+        );
+        assert_eq!(
+            SegmentPattern::parse(":name?.json").unwrap_err(),
+            PatternError::Param
+        );
+        assert_eq!(
+            SegmentPattern::parse("v:version?").unwrap_err(),
+            PatternError::Param
+        );
+        assert_eq!(
+            SegmentPattern::parse(":id>").unwrap_err(),
+            PatternError::Param
+        );
+        assert_eq!(
+            SegmentPattern::parse(":id<int").unwrap_err(),
+            PatternError::Constraint
+        );
+        assert_eq!(
+            SegmentPattern::parse(":id<(>").unwrap_err(),
+            PatternError::Constraint
+        );
+    }
+
+    #[test]
+    fn segment_pattern_constraint_end_test() {
+        let pattern = SegmentPattern::parse(r":year<(?P<y>\d{4})>.html").unwrap();
+
+        assert_eq!(
+            captures(&pattern, "2021.html"),
+            Some(vec![("year", "2021")])
+        );
+        assert_eq!(captures(&pattern, "21.html"), None);
+
+        let pattern = SegmentPattern::parse(r":op<[<>]=>").unwrap();
+
+        assert_eq!(captures(&pattern, ">="), Some(vec![("op", ">=")]));
+
+        // Escaped '>' does not close the constraint
+        let pattern = SegmentPattern::parse(r":word<\w+\>>").unwrap();
+
+        assert_eq!(captures(&pattern, "abc"), Some(vec![("word", "abc")]));
+    }
+
+    #[test]
+    fn segment_pattern_match_test() {
+        let pattern = SegmentPattern::parse(":name.:ext").unwrap();
+
+        assert_eq!(
+            captures(&pattern, "report.pdf"),
+            Some(vec![("name", "report"), ("ext", "pdf")])
+        );
+        assert_eq!(
+            captures(&pattern, "archive.tar.gz"),
+            Some(vec![("name", "archive"), ("ext", "tar.gz")])
+        );
+        assert_eq!(captures(&pattern, "report"), None);
+        assert_eq!(captures(&pattern, ".pdf"), None);
+        assert_eq!(captures(&pattern, "report."), None);
+
+        let pattern = SegmentPattern::parse("v:version").unwrap();
+
+        assert_eq!(captures(&pattern, "v2"), Some(vec![("version", "2")]));
+        assert_eq!(captures(&pattern, "v"), None);
+        assert_eq!(captures(&pattern, "x2"), None);
+
+        let pattern = SegmentPattern::parse(r":name<[a-z.]+>.:ext<int>").unwrap();
+
+        // Backtrack until the constraints are satisfied
+        assert_eq!(
+            captures(&pattern, "backup.tar.2"),
+            Some(vec![("name", "backup.tar"), ("ext", "2")])
+        );
+        assert_eq!(captures(&pattern, "backup.tar"), None);
+    }
+
+    #[test]
+    fn segment_pattern_format_test() {
+        let pattern = SegmentPattern::parse(":name.:ext<[a-z]+>").unwrap();
+
+        assert_eq!(
+            pattern
+                .format(&[("name", "my report"), ("ext", "pdf")])
+                .unwrap(),
+            Some("my%20report.pdf".to_string())
+        );
+        assert!(pattern.format(&[("name", "report")]).is_err());
+        assert!(pattern
+            .format(&[("name", "report"), ("ext", "123")])
+            .is_err());
+
+        let pattern = SegmentPattern::parse(":slug?").unwrap();
+
+        assert_eq!(pattern.format(&[]).unwrap(), None);
+        assert_eq!(
+            pattern.format(&[("slug", "a/b?")]).unwrap(),
+            Some("a%2Fb%3F".to_string())
+        );
+    }
+
+    #[test]
+    fn segment_pattern_ambiguous_test() {
+        let pattern = SegmentPattern::parse(":name.:ext").unwrap();
+
+        assert!(pattern.is_ambiguous_with(&SegmentPattern::parse(":file.:type").unwrap()));
+        assert!(!pattern.is_ambiguous_with(&SegmentPattern::parse(":file-:type").unwrap()));
+        assert!(!pattern.is_ambiguous_with(&SegmentPattern::parse(":file.:type<int>").unwrap()));
+
+        let pattern = SegmentPattern::parse(":id").unwrap();
+
+        assert!(pattern.is_ambiguous_with(&SegmentPattern::parse(":slug?").unwrap()));
+        assert!(!pattern.is_ambiguous_with(&SegmentPattern::parse(":id<int>").unwrap()));
+    }
+}
@@ -0,0 +1,29 @@
+use hyper::StatusCode;
+
+/// Policy for the requests whose trailing slash differs from the registered route path,
+/// such as `/users/` for the route registered at `/users`
+///
+/// Routes ending with a wildcard are not affected since the wildcard captures the trailing slash
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// Serve the route regardless of the trailing slash
+    #[default]
+    Ignore,
+    /// Respond 404 Not Found unless the trailing slash matches the route path
+    Strict,
+    /// Redirect to the route path with 301 Moved Permanently
+    MovedPermanently,
+    /// Redirect to the route path with 308 Permanent Redirect, which keeps the method and body
+    PermanentRedirect,
+}
+
+impl TrailingSlash {
+    /// Status code of the redirection to the route path, None if the policy does not redirect
+    pub fn redirect_status(&self) -> Option<StatusCode> {
+        match self {
+            TrailingSlash::MovedPermanently => Some(StatusCode::MOVED_PERMANENTLY),
+            TrailingSlash::PermanentRedirect => Some(StatusCode::PERMANENT_REDIRECT),
+            TrailingSlash::Ignore | TrailingSlash::Strict => None,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use hyper::{body, Client, StatusCode};
+use obsidian::{context::Context, App, ObsidianError};
+use tokio::{sync::oneshot, time};
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+async fn wait_until_ready(port: u16) {
+    for _ in 0..50 {
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return;
+        }
+        time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("server did not start on port {}", port);
+}
+
+#[tokio::test]
+async fn listen_with_shutdown_stops_on_signal() {
+    let port = free_port();
+    let mut app: App = App::new();
+    app.get("/", |ctx: Context| async { ctx.build("Hello World").ok() });
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(app.listen_with_shutdown(port, async {
+        rx.await.ok();
+    }));
+
+    wait_until_ready(port).await;
+
+    let uri = format!("http://127.0.0.1:{}/", port).parse().unwrap();
+    let res = Client::new().get(uri).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    tx.send(()).unwrap();
+
+    let result = time::timeout(Duration::from_secs(5), server).await;
+    assert!(result.unwrap().unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn listen_with_shutdown_drains_in_flight_requests() {
+    let port = free_port();
+    let mut app: App = App::new();
+    app.get("/slow", |ctx: Context| async {
+        time::sleep(Duration::from_millis(300)).await;
+        ctx.build("Done").ok()
+    });
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(app.listen_with_shutdown(port, async {
+        rx.await.ok();
+    }));
+
+    wait_until_ready(port).await;
+
+    let uri = format!("http://127.0.0.1:{}/slow", port).parse().unwrap();
+    let request = tokio::spawn(async move { Client::new().get(uri).await });
+
+    time::sleep(Duration::from_millis(100)).await;
+    tx.send(()).unwrap();
+
+    let res = request.await.unwrap().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let res_body = body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(res_body, "Done");
+
+    assert!(server.await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn listen_with_shutdown_respects_deadline() {
+    let port = free_port();
+    let mut app: App = App::new();
+    app.set_shutdown_timeout(Duration::from_millis(100));
+    app.get("/hang", |ctx: Context| async {
+        time::sleep(Duration::from_secs(10)).await;
+        ctx.build("Done").ok()
+    });
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(app.listen_with_shutdown(port, async {
+        rx.await.ok();
+    }));
+
+    wait_until_ready(port).await;
+
+    let uri = format!("http://127.0.0.1:{}/hang", port).parse().unwrap();
+    tokio::spawn(async move { Client::new().get(uri).await });
+
+    time::sleep(Duration::from_millis(100)).await;
+    tx.send(()).unwrap();
+
+    let result = time::timeout(Duration::from_secs(5), server).await;
+    match result.unwrap().unwrap() {
+        Err(ObsidianError::ShutdownTimeout(timeout)) => {
+            assert_eq!(timeout, Duration::from_millis(100))
+        }
+        _ => panic!(),
+    }
+}
+
+#[tokio::test]
+async fn listen_with_shutdown_reports_bind_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let app: App = App::new();
+
+    let result = app.listen_with_shutdown(port, std::future::pending()).await;
+
+    assert!(matches!(result, Err(ObsidianError::IoError(_))));
+}
@@ -0,0 +1,67 @@
+//! Count the heap allocations made by a route lookup.
+//! Kept as the only test in this binary so that no other test allocates concurrently.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use obsidian::{
+    context::Context, middleware::logger::Logger, router::Router, ContextResult, Method,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+async fn handler(ctx: Context) -> ContextResult {
+    ctx.build("test").ok()
+}
+
+fn count_allocations(router: &Router, path: &str) -> usize {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let result = router.search_route(path);
+    let after = ALLOCATIONS.load(Ordering::SeqCst);
+
+    assert!(result.is_some());
+    after - before
+}
+
+#[test]
+fn route_lookup_allocations() {
+    let mut router = Router::new();
+
+    router.use_service(Logger::new());
+    router.use_service_to("admin", Logger::new());
+    router.use_service_to("admin/users", Logger::new());
+    router.use_service_to("admin/users/list", Logger::new());
+
+    router.get("admin/users/list", handler);
+    router.post("admin/users/list", handler);
+    router.put("admin/users/list", handler);
+    router.delete("admin/users/list", handler);
+    router.get("admin/members/:id", handler);
+
+    let result = router.search_route("admin/users/list").unwrap();
+    assert_eq!(result.get_middlewares().len(), 4);
+    assert!(result.get_route(&Method::GET).is_some());
+
+    // Neither the resource nor the middlewares are cloned for the lookup
+    assert_eq!(count_allocations(&router, "admin/users/list"), 0);
+
+    // Captured params are borrowed from the path
+    assert_eq!(count_allocations(&router, "admin/members/12"), 0);
+    assert_eq!(count_allocations(&router, "//admin//members/12/"), 0);
+}
@@ -0,0 +1,113 @@
+use std::net::TcpListener;
+use std::time::Duration;
+
+use hyper::{body, header, Body, Client, Method, Request, StatusCode};
+use obsidian::{context::Context, App, ObsidianError};
+use tokio::{sync::oneshot, time};
+
+#[tokio::test]
+async fn serve_on_ephemeral_port() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert_ne!(addr.port(), 0);
+
+    let mut app: App = App::new();
+    app.get("/", |ctx: Context| async { ctx.build("Hello World").ok() });
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(app.serve_with_shutdown(listener, async {
+        rx.await.ok();
+    }));
+
+    // The listener is bound already, so the request is queued until the server accepts it
+    let uri = format!("http://{}/", addr).parse().unwrap();
+    let res = Client::new().get(uri).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res_body = body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(res_body, "Hello World");
+
+    tx.send(()).unwrap();
+
+    let result = time::timeout(Duration::from_secs(5), server).await;
+    assert!(result.unwrap().unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn serve_head_keeps_content_length() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut app: App = App::new();
+    app.get("/", |ctx: Context| async { ctx.build("Hello World").ok() });
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(app.serve_with_shutdown(listener, async {
+        rx.await.ok();
+    }));
+
+    let req = Request::builder()
+        .method(Method::HEAD)
+        .uri(format!("http://{}/", addr))
+        .body(Body::empty())
+        .unwrap();
+    let res = Client::new().request(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get(header::CONTENT_LENGTH).unwrap(), "11");
+
+    let res_body = body::to_bytes(res.into_body()).await.unwrap();
+    assert!(res_body.is_empty());
+
+    tx.send(()).unwrap();
+    assert!(server.await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn bind_reports_invalid_address() {
+    let app: App = App::new();
+
+    let result = app.bind("not-an-address").await;
+
+    assert!(matches!(result, Err(ObsidianError::IoError(_))));
+}
+
+#[tokio::test]
+async fn bind_reports_address_in_use() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app: App = App::new();
+
+    let result = app.bind(addr).await;
+
+    assert!(matches!(result, Err(ObsidianError::IoError(_))));
+}
+
+#[tokio::test]
+async fn serve_static_files_from_scope() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut app: App = App::new();
+    app.scope("assets", |router| {
+        router.use_static_to("/source/", "src/");
+    });
+
+    let (tx, rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(app.serve_with_shutdown(listener, async {
+        rx.await.ok();
+    }));
+
+    let uri = format!("http://{}/assets/source/error/route_error.rs", addr)
+        .parse()
+        .unwrap();
+    let res = Client::new().get(uri).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res_body = body::to_bytes(res.into_body()).await.unwrap();
+    assert!(res_body.starts_with(b"use std::error::Error;"));
+
+    tx.send(()).unwrap();
+
+    let result = time::timeout(Duration::from_secs(5), server).await;
+    assert!(result.unwrap().unwrap().is_ok());
+}